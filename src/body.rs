@@ -3,80 +3,193 @@ use sdl2::pixels::Color;
 use sdl2::rect::Point;
 use fuax_gfx::FauxGFX;
 use cam::Cam;
+use motion::Motion;
+use thermal;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum BodyClass {
+    Massive,
+    // Feels gravity from massive bodies but exerts none
+    Test,
+}
+
+// Identifies a body for as long as it exists, unlike its index in `System::bodies`
+pub type BodyId = u64;
 
 #[derive(Copy, Clone)]
 pub struct Body {
-    pub past_a_x: f32,
-    pub past_a_y: f32,
-    pub past_x: f32,
-    pub past_y: f32,
-    pub x: f32,
-    pub y: f32,
-    pub a_x: f32,
-    pub a_y: f32,
-    pub v_x: f32,
-    pub v_y: f32,
-    pub mass: f32,
-    pub size: f32,
-    pub density: f32,
+    // Assigned when the body is added to a system. The product of a merge keeps the ID of
+    // the body that survived it, and `System::mergers` records what it absorbed.
+    pub id: BodyId,
+    pub past_x: f64,
+    pub past_y: f64,
+    pub past_mult: f64,
+    pub x: f64,
+    pub y: f64,
+    pub a_x: f64,
+    pub a_y: f64,
+    pub v_x: f64,
+    pub v_y: f64,
+    pub mass: f64,
+    pub size: f64,
+    pub density: f64,
     pub color: (u8, u8, u8, u8),
+    pub class: BodyClass,
+    pub motion: Motion,
+    pub charge: f64,
+    // Angular velocity of the body about its centre, counterclockwise, and the angle it
+    // has turned through
+    pub spin: f64,
+    pub rotation: f64,
+    // In kelvin
+    pub temperature: f64,
 }
 
 impl Body {
-    pub fn new(x: f32, y: f32, v_x: f32, v_y: f32, density: f32, size: f32) -> Body {
+    pub fn new(x: f64, y: f64, v_x: f64, v_y: f64, density: f64, size: f64) -> Body {
         Body {
-            past_a_x: 0f32,
-            past_a_y: 0f32,
-            past_x: 0f32,
-            past_y: 0f32,
+            id: 0,
+            past_x: 0f64,
+            past_y: 0f64,
+            past_mult: 0f64,
             x,
             y,
             v_x,
             v_y,
-            a_x: 0f32,
-            a_y: 0f32,
+            a_x: 0f64,
+            a_y: 0f64,
             mass: (4.0 / 3.0) * ::PI * size.powi(3) * density,
             size,
             density,
             color: (255, 255, 255, 255),
+            class: BodyClass::Massive,
+            motion: Motion::Free,
+            charge: 0.0,
+            spin: 0.0,
+            rotation: 0.0,
+            temperature: 0.0,
         }
     }
 
-    pub fn update_self(&mut self, mult: &f32, total_time: &f32) {
-        self.past_x = self.x;
-        self.past_y = self.y;
+    pub fn test_particle(x: f64, y: f64, v_x: f64, v_y: f64, size: f64) -> Body {
+        let mut body = Body::with_mass(x, y, v_x, v_y, 0.0, size);
+        body.class = BodyClass::Test;
+        body
+    }
+
+    // Creates a body whose mass is independent of its radius, e.g. a point mass with
+    // `size` 0 or a diffuse cloud. The density is derived for display.
+    pub fn with_mass(x: f64, y: f64, v_x: f64, v_y: f64, mass: f64, size: f64) -> Body {
+        let mut body = Body::new(x, y, v_x, v_y, 0.0, size);
+        body.mass = mass;
+        body.density = Body::density_of(mass, size);
+        body
+    }
 
-        self.x += (self.v_x * mult) + (0.5 * self.a_x * mult.powi(2));
-        self.y += (self.v_y * mult) + (0.5 * self.a_y * mult.powi(2));
+    pub fn density_of(mass: f64, size: f64) -> f64 {
+        let volume = (4.0 / 3.0) * ::PI * size.powi(3);
+        if volume > 0.0 { mass / volume } else { f64::INFINITY }
+    }
 
-        self.v_x += (self.a_x + self.past_a_x) * mult * 0.5;
-        self.v_y += (self.a_y + self.past_a_y) * mult * 0.5;
+    // Absorbs another body, conserving mass and volume and keeping its own ID. A free
+    // product moves to the centre of mass of the two with their momentum, while bodies
+    // that aren't free stay on their prescribed motion. The spins of both and the angular
+    // momentum of their relative motion about their centre of mass go into the spin of
+    // the product, so off-centre impacts spin it up. Their heat is pooled, while the heat
+    // of the impact itself is left to the caller.
+    pub fn merge(&mut self, other: &Body) {
+        let total_mass = self.mass + other.mass;
+        let reduced_mass = if total_mass > 0.0 { self.mass * other.mass / total_mass } else { 0.0 };
+        let orbital = reduced_mass * ((other.x - self.x) * (other.v_y - self.v_y) - (other.y - self.y) * (other.v_x - self.v_x));
+        let angular_momentum = self.moment_of_inertia() * self.spin + other.moment_of_inertia() * other.spin + orbital;
 
-        self.past_a_x = self.a_x * mult;
-        self.past_a_y = self.a_y * mult;
+        if total_mass > 0.0 {
+            if self.motion.is_free() {
+                self.x = (self.mass * self.x + other.mass * other.x) / total_mass;
+                self.y = (self.mass * self.y + other.mass * other.y) / total_mass;
+                self.v_x = (self.mass * self.v_x + other.mass * other.v_x) / total_mass;
+                self.v_y = (self.mass * self.v_y + other.mass * other.v_y) / total_mass;
+            }
+            self.temperature = (self.mass * self.temperature + other.mass * other.temperature) / total_mass;
+        }
+        self.mass += other.mass;
+        self.size = (self.size.powi(3) + other.size.powi(3)).cbrt();
+        self.density = Body::density_of(self.mass, self.size);
+        let moment_of_inertia = self.moment_of_inertia();
+        self.spin = if moment_of_inertia > 0.0 { angular_momentum / moment_of_inertia } else { 0.0 };
     }
 
-    pub fn compute_gravity(&mut self, body: Body, mult: &f32) {
-        let min_distance = 0.0001;
-        let direction = (body.x - self.x, body.y - self.y);
-        let mut distance = ((body.x - self.x).powi(2) + (body.y - self.y).powi(2)).sqrt();
-        if distance < min_distance {
-            distance = min_distance;
+    // Of a uniform sphere about its centre, so point masses can't hold any spin
+    pub fn moment_of_inertia(&self) -> f64 {
+        0.4 * self.mass * self.size.powi(2)
+    }
+
+    // Velocity at the current position. The leapfrog leaves it half a kick ahead, which is
+    // finished here with the last acceleration.
+    pub fn synchronised_velocity(&self) -> (f64, f64) {
+        if self.motion.is_free() {
+            (self.v_x + 0.5 * self.a_x * self.past_mult, self.v_y + 0.5 * self.a_y * self.past_mult)
+        } else {
+            (self.v_x, self.v_y)
         }
-        let unit_direction = (direction.0 / distance, direction.1 / distance);
-        let force_scalar = ::GRAVITY_CONST * self.mass * body.mass / distance.powi(2);
-        let acc_scalar = force_scalar / self.mass;
-        let acc_vector = (unit_direction.0 * acc_scalar, unit_direction.1 * acc_scalar);
-        self.a_x += acc_vector.0;
-        self.a_y += acc_vector.1;
     }
 
- 
+    pub fn overlaps(&self, other: &Body) -> bool {
+        let rad_sum = (self.size + other.size).powi(2);
+        let distance = (self.x - other.x).powi(2) + (self.y - other.y).powi(2);
+        distance <= rad_sum
+    }
+
+    // Places a body that isn't free on its prescribed position and velocity
+    pub fn set_state(&mut self, state: ((f64, f64), (f64, f64))) {
+        self.past_x = self.x;
+        self.past_y = self.y;
+        self.x = (state.0).0;
+        self.y = (state.0).1;
+        self.v_x = (state.1).0;
+        self.v_y = (state.1).1;
+    }
+
+    pub fn update_self(&mut self, mult: &f64, total_time: &f64) {
+        self.past_x = self.x;
+        self.past_y = self.y;
+
+        // Kick-drift-kick leapfrog: finish the previous step's half kick with the
+        // acceleration at the current position, then start this step's kick and drift
+        self.v_x += 0.5 * self.a_x * (self.past_mult + mult);
+        self.v_y += 0.5 * self.a_y * (self.past_mult + mult);
+
+        self.x += self.v_x * mult;
+        self.y += self.v_y * mult;
+
+        self.past_mult = *mult;
+    }
+
     pub fn render(&self, canvas: &mut WindowCanvas, cam: &Cam) {
         let color_g = 255u8.saturating_sub(self.density as u8);
+        let color = match self.class {
+            BodyClass::Massive => Color::RGBA(255, color_g, 255, 255),
+            BodyClass::Test => Color::RGBA(150, 200, 255, 255),
+        };
+        // Hot bodies glow in their blackbody colour, from the first dull red at the Draper
+        // point of 798 K to fully incandescent at 1500 K
+        let glow = ((self.temperature - 798.0) / (1500.0 - 798.0)).clamp(0.0, 1.0);
+        let color = if glow > 0.0 {
+            let (r, g, b) = thermal::blackbody_color(self.temperature);
+            let blend = |from: u8, to: u8| (from as f64 + glow * (to as f64 - from as f64)) as u8;
+            Color::RGBA(blend(color.r, r), blend(color.g, g), blend(color.b, b), 255)
+        } else {
+            color
+        };
 
         let t = cam.transform((self.x, self.y));
         
-        canvas.filled_circle(Point::new(t.0 as i32, t.1 as i32), (self.size * cam.zoom) as i16, Color::RGBA(255, color_g, 255, 255));
+        canvas.filled_circle(Point::new(t.0 as i32, t.1 as i32), (self.size * cam.zoom) as i16, color);
+
+        // A spoke turning with the body, once it is big enough on screen to show it
+        if self.class == BodyClass::Massive && self.size * cam.zoom >= 3.0 {
+            let rim = cam.transform((self.x + self.size * self.rotation.cos(), self.y + self.size * self.rotation.sin()));
+            canvas.line_rgba(Point::new(t.0 as i32, t.1 as i32), Point::new(rim.0 as i32, rim.1 as i32), Color::RGBA(60, 40, 90, 255)).expect("Failed to draw spin");
+        }
     }
 }
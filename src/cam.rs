@@ -1,7 +1,7 @@
 pub struct Cam {
-    pub x: f32,
-    pub y: f32,
-    pub zoom: f32,
+    pub x: f64,
+    pub y: f64,
+    pub zoom: f64,
 }
 
 impl Cam {
@@ -13,11 +13,11 @@ impl Cam {
         }
     }
 
-    pub fn transform(&self, point: (f32, f32)) -> (f32, f32) {
+    pub fn transform(&self, point: (f64, f64)) -> (f64, f64) {
         return (point.0 * self.zoom - self.x, point.1 * self.zoom - self.y);
     }
 
-    pub fn reverse_transform(&self, t: (f32, f32)) -> (f32, f32) {
+    pub fn reverse_transform(&self, t: (f64, f64)) -> (f64, f64) {
         return ((t.0 + self.x) / self.zoom, (t.1 + self.y) / self.zoom);
     }
 }
@@ -0,0 +1,97 @@
+use body::{Body, BodyClass};
+
+// Escape detection. A free body has escaped once it is further than `radius` from the
+// centre of mass of the other massive bodies, moving away from it with positive energy
+// in their potential, so it will never come back. Escapees are dropped when `remove` is set.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Escape {
+    pub radius: f64,
+    pub remove: bool,
+}
+
+impl Escape {
+    pub fn new(radius: f64, remove: bool) -> Escape {
+        Escape {
+            radius,
+            remove,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.radius > 0.0
+    }
+
+    pub fn name(&self) -> &'static str {
+        match (self.is_enabled(), self.remove) {
+            (false, _) => "OFF",
+            (true, false) => "DETECT",
+            (true, true) => "REMOVE",
+        }
+    }
+
+    pub fn escapees(&self, bodies: &[Body], gravity_const: f64) -> Vec<usize> {
+        if !self.is_enabled() {
+            return vec![];
+        }
+        let massive: Vec<usize> = (0..bodies.len()).filter(|&i| bodies[i].class == BodyClass::Massive && bodies[i].mass > 0.0).collect();
+
+        (0..bodies.len()).filter(|&i| {
+            let body = &bodies[i];
+            if !body.motion.is_free() {
+                return false;
+            }
+            let others: Vec<&Body> = massive.iter().filter(|&&j| j != i).map(|&j| &bodies[j]).collect();
+            let total_mass: f64 = others.iter().map(|other| other.mass).sum();
+            if total_mass <= 0.0 {
+                return false;
+            }
+            let center = others.iter().fold((0.0, 0.0, 0.0, 0.0), |sum, other| {
+                (sum.0 + other.mass * other.x, sum.1 + other.mass * other.y, sum.2 + other.mass * other.v_x, sum.3 + other.mass * other.v_y)
+            });
+            let offset = (body.x - center.0 / total_mass, body.y - center.1 / total_mass);
+            let velocity = (body.v_x - center.2 / total_mass, body.v_y - center.3 / total_mass);
+            let distance = (offset.0.powi(2) + offset.1.powi(2)).sqrt();
+            if distance < self.radius || offset.0 * velocity.0 + offset.1 * velocity.1 <= 0.0 {
+                return false;
+            }
+
+            let potential: f64 = others.iter().map(|other| {
+                let separation = ((body.x - other.x).powi(2) + (body.y - other.y).powi(2)).sqrt();
+                -gravity_const * other.mass / separation
+            }).sum();
+            0.5 * (velocity.0.powi(2) + velocity.1.powi(2)) + potential > 0.0
+        }).collect()
+    }
+}
+
+// Massive bodies that are each other's nearest neighbour and have negative two-body
+// energy. Looser pairings are left out, as in a bound cluster every close pair has
+// negative energy without forming a binary.
+pub fn binaries(bodies: &[Body], gravity_const: f64) -> Vec<(usize, usize)> {
+    let massive: Vec<usize> = (0..bodies.len()).filter(|&i| bodies[i].class == BodyClass::Massive && bodies[i].mass > 0.0).collect();
+    let distance = |i: usize, j: usize| ((bodies[i].x - bodies[j].x).powi(2) + (bodies[i].y - bodies[j].y).powi(2)).sqrt();
+    let nearest: Vec<Option<usize>> = massive.iter().map(|&i| {
+        massive.iter()
+            .cloned()
+            .filter(|&j| j != i)
+            .min_by(|&a, &b| distance(i, a).total_cmp(&distance(i, b)))
+    }).collect();
+
+    let mut pairs = vec![];
+    for (index, &i) in massive.iter().enumerate() {
+        let j = match nearest[index] {
+            Some(j) if j > i => j,
+            _ => continue,
+        };
+        let j_index = massive.iter().position(|&k| k == j).unwrap();
+        if nearest[j_index] != Some(i) {
+            continue;
+        }
+        let v2 = (bodies[i].v_x - bodies[j].v_x).powi(2) + (bodies[i].v_y - bodies[j].v_y).powi(2);
+        let energy = 0.5 * v2 - gravity_const * (bodies[i].mass + bodies[j].mass) / distance(i, j);
+        if energy < 0.0 {
+            pairs.push((i, j));
+        }
+    }
+    pairs
+}
@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+use std::collections::vec_deque::Drain;
+use body::{Body, BodyId};
+use units::UnitSystem;
+
+// Something that happened to the bodies of a `System`, which are referred to by ID
+#[derive(Clone)]
+pub enum Event {
+    BodyAdded(BodyId),
+    // Two bodies touched at the given relative speed
+    Collision { first: BodyId, second: BodyId, impact_speed: f64 },
+    // `absorbed` was merged into `survivor`, which keeps its ID, and will be removed
+    Merged { survivor: BodyId, absorbed: BodyId },
+    // The product of a merge broke up into a remnant keeping its ID and this many fragments
+    Fragmented { remnant: BodyId, fragments: usize },
+    // A body was torn apart by the tides of `primary` and will be removed
+    Disrupted { body: BodyId, primary: BodyId, fragments: usize },
+    // Two bodies became a bound binary
+    Bound(BodyId, BodyId),
+    // A binary came apart without merging
+    Unbound(BodyId, BodyId),
+    // A body left the system for good
+    Escaped(BodyId),
+    // A body was taken out of the simulation, along with its last state
+    Removed(Box<Body>),
+}
+
+impl Event {
+    pub fn describe(&self, units: &UnitSystem) -> String {
+        match *self {
+            Event::BodyAdded(id) => format!("#{} ADDED", id),
+            Event::Collision { first, second, impact_speed } => format!("#{} HIT #{} AT {}", first, second, units.format_velocity(impact_speed)),
+            Event::Merged { survivor, absorbed } => format!("#{} ABSORBED #{}", survivor, absorbed),
+            Event::Fragmented { remnant, fragments } => format!("#{} SHATTERED INTO {} FRAGMENTS", remnant, fragments),
+            Event::Disrupted { body, primary, fragments } => format!("#{} TORN INTO {} PIECES BY #{}", body, fragments, primary),
+            Event::Bound(first, second) => format!("#{} AND #{} BOUND", first, second),
+            Event::Unbound(first, second) => format!("#{} AND #{} UNBOUND", first, second),
+            Event::Escaped(id) => format!("#{} ESCAPED", id),
+            Event::Removed(ref body) => format!("#{} REMOVED WITH {}", body.id, units.format_mass(body.mass)),
+        }
+    }
+}
+
+pub type Subscriber = Box<dyn FnMut(&Event, &UnitSystem)>;
+
+// Events that are kept for a stream nobody drains, beyond which the oldest are dropped
+const MAX_QUEUED: usize = 10_000;
+
+// Events of a system in the order they happened. They are queued until drained, and
+// passed to every subscriber as they are emitted, along with the units of the system at
+// that moment to describe them in. Subscribers see every event, the queue only holds the
+// last `MAX_QUEUED`.
+pub struct EventStream {
+    pub units: UnitSystem,
+    queue: VecDeque<Event>,
+    subscribers: Vec<Subscriber>,
+}
+
+impl EventStream {
+    pub fn new(units: UnitSystem) -> EventStream {
+        EventStream {
+            units,
+            queue: VecDeque::new(),
+            subscribers: vec![],
+        }
+    }
+
+    pub fn emit(&mut self, event: Event) {
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber(&event, &self.units);
+        }
+        if self.queue.len() == MAX_QUEUED {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(event);
+    }
+
+    pub fn drain<'a>(&'a mut self) -> Drain<'a, Event> {
+        self.queue.drain(..)
+    }
+
+    pub fn subscribe<F: FnMut(&Event, &UnitSystem) + 'static>(&mut self, subscriber: F) {
+        self.subscribers.push(Box::new(subscriber));
+    }
+}
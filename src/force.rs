@@ -0,0 +1,112 @@
+use body::Body;
+use softening::Softening;
+
+// Pairwise interaction between bodies. Implementations return the acceleration of
+// `target` caused by `source`.
+pub trait ForceLaw {
+    fn acceleration(&self, target: &Body, source: &Body, gravity_const: f64, softening: &Softening) -> (f64, f64);
+    fn name(&self) -> &'static str;
+}
+
+// Offset from target to source, its length and the softened 1 / r^3 factor
+fn separation(target: &Body, source: &Body, softening: &Softening) -> ((f64, f64), f64, f64) {
+    let direction = (source.x - target.x, source.y - target.y);
+    let distance = (direction.0.powi(2) + direction.1.powi(2)).sqrt();
+    (direction, distance, softening.inverse_cube(distance))
+}
+
+pub struct Newtonian;
+
+impl ForceLaw for Newtonian {
+    fn acceleration(&self, target: &Body, source: &Body, gravity_const: f64, softening: &Softening) -> (f64, f64) {
+        let (direction, _, inverse_cube) = separation(target, source, softening);
+        let acc_scalar = gravity_const * source.mass * inverse_cube;
+        (direction.0 * acc_scalar, direction.1 * acc_scalar)
+    }
+
+    fn name(&self) -> &'static str {
+        "NEWTONIAN"
+    }
+}
+
+// Attraction falling off as 1 / r^exponent
+pub struct PowerLaw {
+    pub exponent: f64,
+}
+
+impl ForceLaw for PowerLaw {
+    fn acceleration(&self, target: &Body, source: &Body, gravity_const: f64, softening: &Softening) -> (f64, f64) {
+        let (direction, distance, inverse_cube) = separation(target, source, softening);
+        if distance <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let acc_scalar = gravity_const * source.mass * inverse_cube * distance.powf(2.0 - self.exponent);
+        (direction.0 * acc_scalar, direction.1 * acc_scalar)
+    }
+
+    fn name(&self) -> &'static str {
+        "POWER LAW"
+    }
+}
+
+// Newtonian gravity screened beyond `length`, the force of the potential -Gm e^(-r/length) / r
+pub struct Yukawa {
+    pub length: f64,
+}
+
+impl ForceLaw for Yukawa {
+    fn acceleration(&self, target: &Body, source: &Body, gravity_const: f64, softening: &Softening) -> (f64, f64) {
+        let (direction, distance, inverse_cube) = separation(target, source, softening);
+        let screening = (1.0 + distance / self.length) * (-distance / self.length).exp();
+        let acc_scalar = gravity_const * source.mass * inverse_cube * screening;
+        (direction.0 * acc_scalar, direction.1 * acc_scalar)
+    }
+
+    fn name(&self) -> &'static str {
+        "YUKAWA"
+    }
+}
+
+// Pairwise MOND: the Newtonian acceleration is boosted with the "simple" interpolating
+// function once it drops below a0
+pub struct Mond {
+    pub a0: f64,
+}
+
+impl ForceLaw for Mond {
+    fn acceleration(&self, target: &Body, source: &Body, gravity_const: f64, softening: &Softening) -> (f64, f64) {
+        let (direction, distance, inverse_cube) = separation(target, source, softening);
+        let newtonian = gravity_const * source.mass * inverse_cube;
+        let magnitude = newtonian * distance;
+        if magnitude <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let boost = 0.5 + (0.25 + self.a0 / magnitude).sqrt();
+        (direction.0 * newtonian * boost, direction.1 * newtonian * boost)
+    }
+
+    fn name(&self) -> &'static str {
+        "MOND"
+    }
+}
+
+// Gravity plus the electrostatic force between charged bodies, with Coulomb constant `k`
+pub struct Coulomb {
+    pub k: f64,
+}
+
+impl ForceLaw for Coulomb {
+    fn acceleration(&self, target: &Body, source: &Body, gravity_const: f64, softening: &Softening) -> (f64, f64) {
+        let (direction, _, inverse_cube) = separation(target, source, softening);
+        let mut acc_scalar = gravity_const * source.mass * inverse_cube;
+        if target.mass > 0.0 {
+            // Like charges repel
+            acc_scalar -= self.k * target.charge * source.charge / target.mass * inverse_cube;
+        }
+        (direction.0 * acc_scalar, direction.1 * acc_scalar)
+    }
+
+    fn name(&self) -> &'static str {
+        "COULOMB"
+    }
+}
@@ -0,0 +1,152 @@
+use body::Body;
+
+// Impact model for collisions of free massive bodies. Below `threshold`, the specific
+// impact energy needed to disperse half of the colliding mass, bodies merge. Above it the
+// mass of the largest remnant falls off with the impact energy following the universal
+// law of Leinhardt & Stewart (2012), and the rest is thrown out as at most `count`
+// fragments. Debris that would make fragments lighter than `min_mass` stays with the
+// remnant. Fragments only merge in impacts until they have flown clear of each other, so
+// they don't shatter endlessly.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Fragmentation {
+    pub threshold: f64,
+    pub count: usize,
+    pub min_mass: f64,
+}
+
+impl Fragmentation {
+    pub fn new(threshold: f64, count: usize, min_mass: f64) -> Fragmentation {
+        Fragmentation {
+            threshold,
+            count,
+            min_mass,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.threshold > 0.0 && self.count > 0
+    }
+
+    // Fraction of the total mass left in the largest remnant at the given specific impact
+    // energy
+    pub fn remnant_fraction(&self, specific_energy: f64) -> f64 {
+        let ratio = specific_energy / self.threshold;
+        if ratio < 1.8 {
+            (1.0 - 0.5 * ratio).min(1.0)
+        } else {
+            // Super-catastrophic disruption
+            0.1 * (ratio / 1.8).powf(-1.5)
+        }
+    }
+
+    // Breaks up `target` after it absorbed `impactor` at `specific_energy` above the
+    // threshold. The target becomes the largest remnant and the fragments are returned,
+    // spread evenly around it and flying apart faster than their escape speed. Mass
+    // is conserved, and the centre of mass, momentum and angular momentum of the merged
+    // body are shared out rather than changed. Point masses have no room for fragments and stay whole.
+    pub fn fragment(&self, target: &mut Body, impactor: &Body, specific_energy: f64, gravity_const: f64) -> Vec<Body> {
+        let total_mass = target.mass;
+        let debris = total_mass * (1.0 - self.remnant_fraction(specific_energy));
+        // Masses fall off as 1 / k, so there are a few large fragments and many small ones
+        let masses_of = |count: usize| {
+            let weight_sum: f64 = (1..count + 1).map(|k| 1.0 / k as f64).sum();
+            (1..count + 1).map(|k| debris / k as f64 / weight_sum).collect::<Vec<f64>>()
+        };
+        let mut count = self.count;
+        while count > 0 && masses_of(count)[count - 1] < self.min_mass {
+            count -= 1;
+        }
+        if count == 0 {
+            return vec![];
+        }
+        let masses = masses_of(count);
+        let remnant_mass = total_mass - masses.iter().sum::<f64>();
+
+        let radius_of = |mass: f64| if target.density.is_finite() && target.density > 0.0 { (mass / target.density / (4.0 / 3.0 * ::PI)).cbrt() } else { 0.0 };
+        let remnant_size = radius_of(remnant_mass);
+        let largest_fragment = radius_of(masses[0]);
+        // Far enough out that the fragments neither touch the remnant nor each other
+        let distance = f64::max(1.5 * (remnant_size + largest_fragment), 1.2 * count as f64 * largest_fragment / ::PI);
+        if distance <= 0.0 {
+            return vec![];
+        }
+        let escape_speed = (2.0 * gravity_const * total_mass / distance).sqrt();
+        let speed = (escape_speed.powi(2) + 2.0 * (specific_energy - self.threshold).max(0.0)).sqrt();
+
+        // Starting from the direction the impactor came from
+        let start = (impactor.y - target.y).atan2(impactor.x - target.x);
+        let mut fragments = vec![];
+        let mut offset = (0.0, 0.0);
+        let mut momentum = (0.0, 0.0);
+        for (k, &mass) in masses.iter().enumerate() {
+            let angle = start + 2.0 * ::PI * k as f64 / count as f64;
+            let direction = (angle.cos(), angle.sin());
+            let mut fragment = Body::with_mass(
+                target.x + distance * direction.0,
+                target.y + distance * direction.1,
+                target.v_x + speed * direction.0,
+                target.v_y + speed * direction.1,
+                mass,
+                radius_of(mass));
+            fragment.charge = target.charge * mass / total_mass;
+            fragment.temperature = target.temperature;
+            offset.0 += mass * distance * direction.0;
+            offset.1 += mass * distance * direction.1;
+            momentum.0 += mass * speed * direction.0;
+            momentum.1 += mass * speed * direction.1;
+            fragments.push(fragment);
+        }
+
+        let spin_momentum = target.moment_of_inertia() * target.spin;
+        target.charge *= remnant_mass / total_mass;
+        target.mass = remnant_mass;
+        target.size = remnant_size;
+
+        // The pieces fly straight out, so their spin keeps the angular momentum of the spin
+        // of the merged body, shared out by moment of inertia
+        let moment_of_inertia = fragments.iter().fold(target.moment_of_inertia(), |sum, fragment| sum + fragment.moment_of_inertia());
+        let spin = if moment_of_inertia > 0.0 { spin_momentum / moment_of_inertia } else { 0.0 };
+        target.spin = spin;
+        for fragment in fragments.iter_mut() {
+            fragment.spin = spin;
+        }
+
+        // Shift everything together to keep the centre of mass and its motion where they were
+        for body in fragments.iter_mut().chain(Some(target)) {
+            body.x -= offset.0 / total_mass;
+            body.y -= offset.1 / total_mass;
+            body.v_x -= momentum.0 / total_mass;
+            body.v_y -= momentum.1 / total_mass;
+        }
+        fragments
+    }
+}
+
+// Time after an impact during which its fragments are spared from shattering again, so
+// they don't break each other up endlessly, while they fly out from `remnant` to ten
+// times the distance they started at
+pub fn cooldown(remnant: &Body, fragments: &[Body]) -> f64 {
+    fragments.iter().map(|fragment| {
+        let distance = ((fragment.x - remnant.x).powi(2) + (fragment.y - remnant.y).powi(2)).sqrt();
+        let speed = ((fragment.v_x - remnant.v_x).powi(2) + (fragment.v_y - remnant.v_y).powi(2)).sqrt();
+        if speed > 0.0 { 9.0 * distance / speed } else { 0.0 }
+    }).fold(0.0, f64::max)
+}
+
+// Kinetic energy of `bodies` relative to the motion of their centre of mass
+pub fn internal_kinetic_energy(bodies: &[Body]) -> f64 {
+    let mass: f64 = bodies.iter().map(|body| body.mass).sum();
+    if mass <= 0.0 {
+        return 0.0;
+    }
+    let v_x = bodies.iter().map(|body| body.mass * body.v_x).sum::<f64>() / mass;
+    let v_y = bodies.iter().map(|body| body.mass * body.v_y).sum::<f64>() / mass;
+    bodies.iter().map(|body| 0.5 * body.mass * ((body.v_x - v_x).powi(2) + (body.v_y - v_y).powi(2))).sum()
+}
+
+// Kinetic energy of the collision in the centre of mass frame per unit of total mass
+pub fn specific_impact_energy(first: &Body, second: &Body) -> f64 {
+    let total_mass = first.mass + second.mass;
+    let speed_squared = (first.v_x - second.v_x).powi(2) + (first.v_y - second.v_y).powi(2);
+    0.5 * first.mass * second.mass / total_mass.powi(2) * speed_squared
+}
@@ -12,6 +12,7 @@ pub trait FauxGFX {
 	fn hline(&mut self, x1: i32, x2: i32, y: i32) -> Result<(), String>;
 	fn hline_rgba(&mut self, x1: i32, x2: i32, y: i32, color: Color) -> Result<(), String>;
 	fn ellipse_rgba(&mut self, p: Point, radius_x: i16, radius_y: i16, color: Color, f: i32) -> Result<(), String>;
+	fn rotated_ellipse_rgba(&mut self, p: Point, radius_x: f64, radius_y: f64, angle: f64, color: Color) -> Result<(), String>;
 	fn filled_circle(&mut self, p: Point, rad: i16, color: Color) -> Result<(), String>;
 	fn filled_polygon_rgba_mt(&mut self, verts: Vec<Point>, color: Color) -> Result<(), String>;
 	fn thick_line(&mut self, start: Point, end: Point, width: i32, color: Color)-> Result<(), String>;
@@ -478,6 +479,31 @@ impl FauxGFX for WindowCanvas {
 	}
 
 
+	// Outline of an ellipse whose x axis is turned by `angle` radians, drawn as a polygon
+	// with enough sides for the segments to stay a few pixels long
+	fn rotated_ellipse_rgba(&mut self, p: Point, radius_x: f64, radius_y: f64, angle: f64, color: Color) -> Result<(), String> {
+		if radius_x < 0.0 || radius_y < 0.0 {
+			return Err(String::from("Cannot draw ellipse with radius less then 0"));
+		}
+
+		let perimeter = 2.0 * std::f64::consts::PI * ((radius_x.powi(2) + radius_y.powi(2)) / 2.0).sqrt();
+		let segments = ((perimeter / 4.0) as i32).clamp(16, 2048);
+		let (sin_a, cos_a) = angle.sin_cos();
+		let vertex = |i: i32| {
+			let t = 2.0 * std::f64::consts::PI * i as f64 / segments as f64;
+			let (x, y) = (radius_x * t.cos(), radius_y * t.sin());
+			Point::new(p.x + (x * cos_a - y * sin_a).round() as i32, p.y + (x * sin_a + y * cos_a).round() as i32)
+		};
+
+		let mut previous = vertex(0);
+		for i in 1..=segments {
+			let next = vertex(i);
+			self.line_rgba(previous, next, color)?;
+			previous = next;
+		}
+		Ok(())
+	}
+
 	fn filled_circle(&mut self, p: Point, rad: i16, color: Color) -> Result<(), String> {
 		return self.ellipse_rgba(p, rad, rad, color, 1);
 	}
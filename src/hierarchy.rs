@@ -0,0 +1,100 @@
+use body::{Body, BodyClass};
+
+// Radius within which a body's gravity dominates the motion of its neighbours over that
+// of the body it orbits
+#[derive(Copy, Clone, PartialEq)]
+pub enum Influence {
+    // Laplace sphere of influence, r = d (m / M)^(2/5)
+    Laplace,
+    // Hill sphere, r = d (m / 3M)^(1/3)
+    Hill,
+}
+
+impl Influence {
+    pub fn radius(&self, distance: f64, mass: f64, parent_mass: f64) -> f64 {
+        match *self {
+            Influence::Laplace => distance * (mass / parent_mass).powf(0.4),
+            Influence::Hill => distance * (mass / (3.0 * parent_mass)).cbrt(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Influence::Laplace => "LAPLACE",
+            Influence::Hill => "HILL",
+        }
+    }
+
+    pub fn next(&self) -> Influence {
+        match *self {
+            Influence::Laplace => Influence::Hill,
+            Influence::Hill => Influence::Laplace,
+        }
+    }
+}
+
+// Which body every body orbits, e.g. star -> planet -> moon. Each body belongs to the
+// innermost sphere of influence of a more massive body that contains it, so a moon is
+// assigned to its planet even where the star pulls on it harder.
+pub struct Hierarchy {
+    pub parents: Vec<Option<usize>>,
+    // Sphere of influence of every body, infinite for bodies without a parent and zero
+    // for test particles
+    pub radii: Vec<f64>,
+}
+
+impl Hierarchy {
+    pub fn new(bodies: &[Body], influence: Influence) -> Hierarchy {
+        let mass = |i: usize| if bodies[i].class == BodyClass::Massive { bodies[i].mass.max(0.0) } else { 0.0 };
+        let distance = |i: usize, j: usize| ((bodies[i].x - bodies[j].x).powi(2) + (bodies[i].y - bodies[j].y).powi(2)).sqrt();
+
+        // Parents are always heavier, so their spheres are known by the time they're needed
+        let mut order: Vec<usize> = (0..bodies.len()).collect();
+        order.sort_by(|&a, &b| mass(b).total_cmp(&mass(a)));
+
+        let mut parents = vec![None; bodies.len()];
+        let mut radii = vec![0.0; bodies.len()];
+        let mut placed: Vec<usize> = vec![];
+        for &i in &order {
+            let parent = placed.iter()
+                .cloned()
+                .filter(|&j| mass(j) > mass(i) && distance(i, j) < radii[j])
+                .min_by(|&a, &b| radii[a].total_cmp(&radii[b]));
+
+            parents[i] = parent;
+            radii[i] = match parent {
+                _ if mass(i) <= 0.0 => 0.0,
+                Some(parent) => influence.radius(distance(i, parent), mass(i), mass(parent)),
+                None => f64::INFINITY,
+            };
+            if mass(i) > 0.0 {
+                placed.push(i);
+            }
+        }
+
+        Hierarchy {
+            parents,
+            radii,
+        }
+    }
+
+    // Bodies orbiting the body at `index` directly
+    pub fn children(&self, index: usize) -> Vec<usize> {
+        (0..self.parents.len()).filter(|&i| self.parents[i] == Some(index)).collect()
+    }
+
+    // Chain of parents from the body at `index` up to the top of its tree, nearest first
+    pub fn ancestors(&self, index: usize) -> Vec<usize> {
+        let mut ancestors = vec![];
+        let mut current = self.parents[index];
+        while let Some(parent) = current {
+            ancestors.push(parent);
+            current = self.parents[parent];
+        }
+        ancestors
+    }
+
+    pub fn depth(&self, index: usize) -> usize {
+        self.ancestors(index).len()
+    }
+}
@@ -0,0 +1,310 @@
+use body::{Body, BodyClass};
+use system::System;
+use orbit;
+
+// (x, y, v_x, v_y) of every body
+pub type State = Vec<[f64; 4]>;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Integrator {
+    // Kick-drift-kick leapfrog with one step per frame
+    Leapfrog,
+    // Embedded Runge-Kutta 5(4) with error control, substepping each frame
+    DormandPrince(AdaptiveStep),
+    // Symplectic Kepler drift and interaction kick around the most massive body, in steps
+    // no longer than `max_step` (one per frame when 0)
+    WisdomHolman { max_step: f64 },
+}
+
+impl Integrator {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Integrator::Leapfrog => "LEAPFROG",
+            Integrator::DormandPrince(_) => "DORMAND-PRINCE",
+            Integrator::WisdomHolman { .. } => "WISDOM-HOLMAN",
+        }
+    }
+
+    pub fn is_wisdom_holman(&self) -> bool {
+        matches!(*self, Integrator::WisdomHolman { .. })
+    }
+
+    pub fn next(&self) -> Integrator {
+        match *self {
+            Integrator::Leapfrog => Integrator::DormandPrince(AdaptiveStep::new(1e-8)),
+            Integrator::DormandPrince(_) => Integrator::WisdomHolman { max_step: 0.0 },
+            Integrator::WisdomHolman { .. } => Integrator::Leapfrog,
+        }
+    }
+
+    // Prepares `bodies` for switching to `next`, `accelerations` being theirs at their
+    // current positions. The leapfrog leaves velocities half a kick ahead, which is
+    // finished on the way out, and starts from synchronised ones without a kick pending.
+    pub fn hand_over(&self, next: &Integrator, bodies: &mut [Body], accelerations: &[(f64, f64)]) {
+        let leaving = *self == Integrator::Leapfrog && *next != Integrator::Leapfrog;
+        for (i, body) in bodies.iter_mut().enumerate() {
+            if leaving && body.motion.is_free() {
+                body.v_x += 0.5 * accelerations[i].0 * body.past_mult;
+                body.v_y += 0.5 * accelerations[i].1 * body.past_mult;
+            }
+            body.past_mult = 0.0;
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct AdaptiveStep {
+    pub tolerance: f64,
+    // Step size the controller wants to try next
+    pub step: f64,
+    // Number of steps taken during the last frame
+    pub substeps: usize,
+    // Whether the last frame ran out of attempts and left the rest of its interval out
+    pub forced: bool,
+}
+
+impl AdaptiveStep {
+    pub fn new(tolerance: f64) -> AdaptiveStep {
+        AdaptiveStep {
+            tolerance,
+            step: 0.0,
+            substeps: 0,
+            forced: false,
+        }
+    }
+}
+
+const MAX_SUBSTEPS: usize = 10_000;
+
+const C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const A: [[f64; 6]; 7] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+    [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+    [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+// Difference between the fifth and fourth order weights
+const E: [f64; 7] = [71.0 / 57600.0, 0.0, -71.0 / 16695.0, 71.0 / 1920.0, -17253.0 / 339200.0, 22.0 / 525.0, -1.0 / 40.0];
+
+// Advances `state` from `start_time` by `interval`, taking as many steps as needed to keep
+// the estimated local error of every component below the tolerance. Steps that can't be
+// made accurate enough within `MAX_SUBSTEPS` attempts are never accepted, the rest of the
+// interval is left out instead and `forced` is set.
+pub fn dormand_prince<F>(state: &mut State, start_time: f64, interval: f64, adaptive: &mut AdaptiveStep, mut derivative: F)
+    where F: FnMut(&[[f64; 4]], f64) -> State
+{
+    let end_time = start_time + interval;
+    let mut time = start_time;
+    let mut next_step = if adaptive.step > 0.0 { adaptive.step } else { interval };
+    let mut attempts = 0;
+    adaptive.substeps = 0;
+    adaptive.forced = false;
+
+    let mut k = vec![derivative(state, time)];
+    while end_time - time > interval * 1e-12 {
+        if attempts >= MAX_SUBSTEPS {
+            adaptive.forced = true;
+            break;
+        }
+        attempts += 1;
+        let truncated = next_step >= end_time - time;
+        let h = if truncated { end_time - time } else { next_step };
+
+        k.truncate(1);
+        for stage in 1..6 {
+            let stage_state = combine(state, &k, &A[stage], h);
+            k.push(derivative(&stage_state, time + C[stage] * h));
+        }
+        // The last stage is evaluated at the fifth order solution and reused as the first
+        // stage of the next step
+        let candidate = combine(state, &k, &A[6], h);
+        k.push(derivative(&candidate, time + C[6] * h));
+
+        let mut error: f64 = 0.0;
+        for (i, body) in candidate.iter().enumerate() {
+            for c in 0..4 {
+                let estimate = h * (0..7).map(|s| E[s] * k[s][i][c]).sum::<f64>();
+                let scale = adaptive.tolerance * (1.0 + body[c].abs().max(state[i][c].abs()));
+                // A step that overflowed can't be trusted however small its estimate
+                error = if estimate.is_finite() && body[c].is_finite() { error.max((estimate / scale).abs()) } else { f64::INFINITY };
+            }
+        }
+
+        let factor = if error > 0.0 { (0.9 * error.powf(-0.2)).clamp(0.2, 5.0) } else { 5.0 };
+        if error <= 1.0 {
+            time = if truncated { end_time } else { time + h };
+            *state = candidate;
+            let last = k.pop().unwrap();
+            k[0] = last;
+            adaptive.substeps += 1;
+            if !truncated || factor < 1.0 {
+                next_step = h * factor;
+            }
+        } else {
+            next_step = h * factor;
+        }
+    }
+    adaptive.step = next_step;
+}
+
+fn combine(state: &[[f64; 4]], k: &[State], weights: &[f64], h: f64) -> State {
+    let mut result = state.to_vec();
+    for (s, derivative) in k.iter().enumerate() {
+        if weights[s] == 0.0 {
+            continue;
+        }
+        for (value, rate) in result.iter_mut().zip(derivative) {
+            for c in 0..4 {
+                value[c] += h * weights[s] * rate[c];
+            }
+        }
+    }
+    result
+}
+
+// Wisdom-Holman map in democratic heliocentric coordinates: positions relative to the
+// primary and velocities relative to the centre of mass. The Kepler problem around the
+// primary is solved exactly and everything else, including fields and alternative force
+// laws, is applied as kicks. Returns false when there is no free massive body to use as
+// the primary.
+pub fn wisdom_holman(system: &System, bodies: &mut [Body], start_time: f64, interval: f64, max_step: f64) -> bool {
+    let mass = |body: &Body| if body.class == BodyClass::Massive { body.mass } else { 0.0 };
+    let primary = match wisdom_holman_primary(bodies) {
+        Some(primary) => primary,
+        None => return false,
+    };
+    let others: Vec<usize> = (0..bodies.len()).filter(|&i| i != primary && bodies[i].motion.is_free()).collect();
+
+    let primary_mass = bodies[primary].mass;
+    let total_mass = primary_mass + others.iter().map(|&i| mass(&bodies[i])).sum::<f64>();
+    let mu = system.gravity_const * primary_mass;
+
+    let weighted = |values: &dyn Fn(&Body) -> (f64, f64)| {
+        let mut sum = (0.0, 0.0);
+        for &i in others.iter().chain(Some(primary).iter()) {
+            let value = values(&bodies[i]);
+            sum.0 += mass(&bodies[i]) * value.0;
+            sum.1 += mass(&bodies[i]) * value.1;
+        }
+        (sum.0 / total_mass, sum.1 / total_mass)
+    };
+    let mut center = weighted(&|body| (body.x, body.y));
+    let mut center_velocity = weighted(&|body| (body.v_x, body.v_y));
+    let origin = (bodies[primary].x, bodies[primary].y);
+    let mut positions: Vec<(f64, f64)> = others.iter().map(|&i| (bodies[i].x - origin.0, bodies[i].y - origin.1)).collect();
+    let mut velocities: Vec<(f64, f64)> = others.iter().map(|&i| (bodies[i].v_x - center_velocity.0, bodies[i].v_y - center_velocity.1)).collect();
+
+    let steps = if max_step > 0.0 { (interval / max_step).ceil().max(1.0) as usize } else { 1 };
+    let h = interval / steps as f64;
+    let mut time = start_time;
+
+    // Writes the barycentric state back into the bodies
+    let write_back = |bodies: &mut [Body], positions: &[(f64, f64)], velocities: &[(f64, f64)], center: (f64, f64), center_velocity: (f64, f64), time: f64| {
+        let mut offset = (0.0, 0.0);
+        let mut momentum = (0.0, 0.0);
+        for (k, &i) in others.iter().enumerate() {
+            offset.0 += mass(&bodies[i]) * positions[k].0 / total_mass;
+            offset.1 += mass(&bodies[i]) * positions[k].1 / total_mass;
+            momentum.0 += mass(&bodies[i]) * velocities[k].0 / primary_mass;
+            momentum.1 += mass(&bodies[i]) * velocities[k].1 / primary_mass;
+        }
+        let origin = (center.0 - offset.0, center.1 - offset.1);
+        bodies[primary].x = origin.0;
+        bodies[primary].y = origin.1;
+        bodies[primary].v_x = center_velocity.0 - momentum.0;
+        bodies[primary].v_y = center_velocity.1 - momentum.1;
+        for (k, &i) in others.iter().enumerate() {
+            bodies[i].x = origin.0 + positions[k].0;
+            bodies[i].y = origin.1 + positions[k].1;
+            bodies[i].v_x = center_velocity.0 + velocities[k].0;
+            bodies[i].v_y = center_velocity.1 + velocities[k].1;
+        }
+        for body in bodies.iter_mut() {
+            if let Some(state) = system.prescribed_state(&body.motion, time) {
+                body.set_state(state);
+            }
+        }
+    };
+
+    // Accelerations of the written back bodies with the Kepler part and the acceleration of
+    // the centre of mass removed, and the acceleration of the centre of mass
+    let interactions = |bodies: &mut [Body], positions: &[(f64, f64)], time: f64| {
+        let accelerations = system.accelerations(bodies, time);
+        for (body, acceleration) in bodies.iter_mut().zip(&accelerations) {
+            body.a_x = acceleration.0;
+            body.a_y = acceleration.1;
+        }
+        let mut center_acceleration = (0.0, 0.0);
+        for &i in others.iter().chain(Some(primary).iter()) {
+            center_acceleration.0 += mass(&bodies[i]) * accelerations[i].0 / total_mass;
+            center_acceleration.1 += mass(&bodies[i]) * accelerations[i].1 / total_mass;
+        }
+        let kicks: Vec<(f64, f64)> = others.iter().enumerate().map(|(k, &i)| {
+            let r3 = (positions[k].0.powi(2) + positions[k].1.powi(2)).powf(1.5);
+            let kepler = if r3 > 0.0 { (-mu * positions[k].0 / r3, -mu * positions[k].1 / r3) } else { (0.0, 0.0) };
+            (accelerations[i].0 - kepler.0 - center_acceleration.0, accelerations[i].1 - kepler.1 - center_acceleration.1)
+        }).collect();
+        (kicks, center_acceleration)
+    };
+
+    write_back(bodies, &positions, &velocities, center, center_velocity, time);
+    let (mut kicks, mut center_acceleration) = interactions(bodies, &positions, time);
+    for _ in 0..steps {
+        // Half kick, half jump, Kepler drift, half jump, half kick
+        for (velocity, kick) in velocities.iter_mut().zip(&kicks) {
+            velocity.0 += 0.5 * h * kick.0;
+            velocity.1 += 0.5 * h * kick.1;
+        }
+        center_velocity.0 += 0.5 * h * center_acceleration.0;
+        center_velocity.1 += 0.5 * h * center_acceleration.1;
+
+        jump(&mut positions, &velocities, &others, bodies, primary_mass, 0.5 * h);
+        for (position, velocity) in positions.iter_mut().zip(velocities.iter_mut()) {
+            let (new_position, new_velocity) = orbit::kepler_drift(*position, *velocity, mu, h);
+            *position = new_position;
+            *velocity = new_velocity;
+        }
+        center.0 += h * center_velocity.0;
+        center.1 += h * center_velocity.1;
+        jump(&mut positions, &velocities, &others, bodies, primary_mass, 0.5 * h);
+        time += h;
+
+        write_back(bodies, &positions, &velocities, center, center_velocity, time);
+        let (new_kicks, new_center_acceleration) = interactions(bodies, &positions, time);
+        kicks = new_kicks;
+        center_acceleration = new_center_acceleration;
+        for (velocity, kick) in velocities.iter_mut().zip(&kicks) {
+            velocity.0 += 0.5 * h * kick.0;
+            velocity.1 += 0.5 * h * kick.1;
+        }
+        center_velocity.0 += 0.5 * h * center_acceleration.0;
+        center_velocity.1 += 0.5 * h * center_acceleration.1;
+    }
+    write_back(bodies, &positions, &velocities, center, center_velocity, time);
+    true
+}
+
+// The most massive free body, which the other bodies orbit in the Wisdom-Holman map
+pub fn wisdom_holman_primary(bodies: &[Body]) -> Option<usize> {
+    (0..bodies.len())
+        .filter(|&i| bodies[i].class == BodyClass::Massive && bodies[i].motion.is_free())
+        .max_by(|&a, &b| bodies[a].mass.partial_cmp(&bodies[b].mass).unwrap())
+}
+
+// Moves every body by the total momentum of the bodies orbiting the primary
+fn jump(positions: &mut [(f64, f64)], velocities: &[(f64, f64)], others: &[usize], bodies: &[Body], primary_mass: f64, dt: f64) {
+    let mut momentum = (0.0, 0.0);
+    for (k, &i) in others.iter().enumerate() {
+        if bodies[i].class == BodyClass::Massive {
+            momentum.0 += bodies[i].mass * velocities[k].0;
+            momentum.1 += bodies[i].mass * velocities[k].1;
+        }
+    }
+    for position in positions.iter_mut() {
+        position.0 += dt * momentum.0 / primary_mass;
+        position.1 += dt * momentum.1 / primary_mass;
+    }
+}
@@ -0,0 +1,67 @@
+use body::{Body, BodyClass};
+
+// The five Lagrange points of a pair of bodies, where a test particle co-rotating with
+// the pair feels no net force, assuming the pair is on a circular orbit at its current
+// separation. Each point comes with the velocity of that co-rotation.
+//
+// In units of the separation, with the barycentre at the origin, the heavier body at -mu
+// and the lighter one at 1 - mu for mass ratio mu = m2 / (m1 + m2), L1 lies between the
+// two, L2 beyond the lighter body and L3 beyond the heavier one. L4 leads the lighter
+// body by 60 degrees along its orbit and L5 trails it.
+pub fn lagrange_points(first: &Body, second: &Body) -> [((f64, f64), (f64, f64)); 5] {
+    let mass = |body: &Body| if body.class == BodyClass::Massive { body.mass.max(0.0) } else { 0.0 };
+    let (primary, secondary) = if mass(first) >= mass(second) { (first, second) } else { (second, first) };
+    let total_mass = mass(primary) + mass(secondary);
+    let mu = if total_mass > 0.0 { mass(secondary) / total_mass } else { 0.0 };
+
+    let offset = (secondary.x - primary.x, secondary.y - primary.y);
+    let separation = (offset.0.powi(2) + offset.1.powi(2)).sqrt();
+    let velocity = (secondary.v_x - primary.v_x, secondary.v_y - primary.v_y);
+    let angular_momentum = offset.0 * velocity.1 - offset.1 * velocity.0;
+    // Angular velocity of the line joining the pair
+    let omega = if separation > 0.0 { angular_momentum / separation.powi(2) } else { 0.0 };
+
+    let center = (primary.x + mu * offset.0, primary.y + mu * offset.1);
+    let center_velocity = (primary.v_x + mu * velocity.0, primary.v_y + mu * velocity.1);
+    let axis = if separation > 0.0 { (offset.0 / separation, offset.1 / separation) } else { (1.0, 0.0) };
+    // Perpendicular pointing along the motion of the lighter body
+    let side = if angular_momentum < 0.0 { -1.0 } else { 1.0 };
+    let normal = (-side * axis.1, side * axis.0);
+
+    let height = 0.75f64.sqrt();
+    let frame_points = [
+        (collinear_point(mu, -mu, 1.0 - mu), 0.0),
+        (collinear_point(mu, 1.0 - mu, 2.0), 0.0),
+        (collinear_point(mu, -2.0, -mu), 0.0),
+        (0.5 - mu, height),
+        (0.5 - mu, -height),
+    ];
+
+    let mut points = [((0.0, 0.0), (0.0, 0.0)); 5];
+    for (point, &(x, y)) in points.iter_mut().zip(frame_points.iter()) {
+        let position = (separation * (x * axis.0 + y * normal.0), separation * (x * axis.1 + y * normal.1));
+        *point = (
+            (center.0 + position.0, center.1 + position.1),
+            (center_velocity.0 - omega * position.1, center_velocity.1 + omega * position.0),
+        );
+    }
+    points
+}
+
+// Root of the net force along the axis between `low` and `high`, where it is monotonic
+fn collinear_point(mu: f64, low: f64, high: f64) -> f64 {
+    let force = |x: f64| {
+        let (to_primary, to_secondary) = (x + mu, x - 1.0 + mu);
+        x - (1.0 - mu) * to_primary / to_primary.abs().powi(3) - mu * to_secondary / to_secondary.abs().powi(3)
+    };
+    let (mut low, mut high) = (low, high);
+    for _ in 0..100 {
+        let middle = 0.5 * (low + high);
+        if force(middle) < 0.0 {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    0.5 * (low + high)
+}
@@ -6,19 +6,46 @@ mod system;
 mod cam;
 mod gui;
 mod fuax_gfx;
+mod softening;
+mod units;
+mod scenario;
+mod motion;
+mod orbit;
+mod potential;
+mod force;
+mod relativity;
+mod integrator;
+mod regularization;
+mod hierarchy;
+mod escape;
+mod event;
+mod merger;
+mod fragmentation;
+mod lagrange;
+mod rotating;
+mod roche;
+mod spin;
+mod thermal;
 
 use sdl2::pixels::Color;
 use sdl2::keyboard::Keycode;
 use sdl2::keyboard::KeyboardState;
-use sdl2::mouse::MouseState;
+use sdl2::mouse::{MouseButton, MouseState};
 use sdl2::keyboard::Scancode;
+use sdl2::keyboard::{LSHIFTMOD, RSHIFTMOD};
 use sdl2::event::Event;
 use sdl2::rect::Point;
 use stopwatch::Stopwatch;
 use fuax_gfx::FauxGFX;
+use scenario::Scenario;
+use body::{Body, BodyId};
+use integrator::Integrator;
+use escape::Escape;
+// Not to be confused with the SDL events of the window
+use event::Event as BodyEvent;
+use std::env;
 
-const GRAVITY_CONST: f32 = 0.0005;
-const PI: f32 = 3.14159265;
+const PI: f64 = std::f64::consts::PI;
 
 fn main() {
     let mut cam = cam::Cam::new();
@@ -37,25 +64,46 @@ fn main() {
     let mut show_hud = true;
 
     let draw_size = window.drawable_size();
-    res_mult = draw_size.0 as f32 / window.size().0 as f32;
+    res_mult = draw_size.0 as f64 / window.size().0 as f64;
 
     let mut canvas = window.into_canvas().accelerated().build().unwrap();
     let mut event_pump = sdl_context.event_pump().unwrap();
     let mut system = system::System::new();
+    let args: Vec<String> = env::args().skip(1).collect();
+    // `--events` prints everything that happens to the bodies as it happens
+    let log_events = args.iter().any(|arg| arg == "--events");
+    let scenario_path = args.into_iter().find(|arg| !arg.starts_with("--"));
+    if log_events {
+        subscribe_logger(&mut system);
+    }
 
     // Editor Speeds
     let density_speed = 0.1;
+    let mass_speed = 0.01f64;
 
     // Editor
-    let mut selected_size: f32 = 50.0;
-    let mut selected_pos = (0f32, 0f32);
-    let mut selected_vel = (0f32, 0f32);
-    let mut selected_density = 1f32;
+    let mut selected_size: f64 = 50.0;
+    let mut selected_pos = (0f64, 0f64);
+    let mut selected_vel = (0f64, 0f64);
+    let mut selected_density = 1f64;
+    let mut selected_mass: Option<f64> = None;
+    let mut test_brush = false;
+    let mut pinned_brush = false;
+    let mut selected_charge = 0f64;
     let mut pos_selected = false;
+    let mut softening_length = 5f64;
+    let mut selected_body: Option<BodyId> = None;
+    // Second body of the pair whose Lagrange points are shown, the parent of the selected
+    // body in the hierarchy when not picked explicitly
+    let mut selected_partner: Option<BodyId> = None;
+    let mut show_influence = false;
+    let mut show_lagrange = false;
+    let mut ejections = 0;
+    let mut last_event = String::new();
 
-    let mut mouse_x = 0f32;
-    let mut mouse_y = 0f32;
-    let mut raw_zoom = 0f32;
+    let mut mouse_x = 0f64;
+    let mut mouse_y = 0f64;
+    let mut raw_zoom = 0f64;
 
     let mut mouse_pressed = false;
     let mut pmouse_pressed = false;
@@ -67,7 +115,15 @@ fn main() {
     // FPS
     let mut fps_sw = Stopwatch::start_new();
 
-    let mut total_time = 0f32;
+    let mut total_time = 0f64;
+
+    if let Some(ref path) = scenario_path {
+        let scenario = Scenario::load(path).expect("Failed to load scenario");
+        raw_zoom = apply_scenario(scenario, &mut system, &mut cam, draw_size);
+        if log_events {
+            subscribe_logger(&mut system);
+        }
+    }
 
     'running: loop {
         //FPS and Time Mult
@@ -76,8 +132,9 @@ fn main() {
             elapsed_nanos = 1;
         }
         let fps = 1_000_000_000 / elapsed_nanos;
-        let time_mult = (elapsed_nanos as f32) * 400.0 / 1_000_000_000f32;
-        total_time += time_mult;
+        let time_mult = (elapsed_nanos as f64) * 400.0 / 1_000_000_000f64;
+        let sim_mult = (elapsed_nanos as f64) * system.time_scale / 1_000_000_000f64;
+        total_time += sim_mult;
         fps_sw.restart();
 
         // Events
@@ -88,17 +145,122 @@ fn main() {
                     break 'running
                 },
                 Event::KeyDown {keycode: Some(Keycode::R), ..} => {
-                    system.bodies = vec!();
+                    system.clear();
                     cam.zoom = 1.0;
                     cam.x = 0.0;
                     cam.y = 0.0;
                     raw_zoom = 0.0;
+                    total_time = 0.0;
+                    selected_body = None;
+                    selected_partner = None;
+                    ejections = 0;
+                    last_event = String::new();
+                },
+                Event::KeyDown {keycode: Some(Keycode::L), ..} => {
+                    if let Some(ref path) = scenario_path {
+                        match Scenario::load(path) {
+                            Ok(scenario) => {
+                                raw_zoom = apply_scenario(scenario, &mut system, &mut cam, draw_size);
+                                if log_events {
+                                    subscribe_logger(&mut system);
+                                }
+                                total_time = 0.0;
+                                selected_body = None;
+                                selected_partner = None;
+                                ejections = 0;
+                                last_event = String::new();
+                            },
+                            Err(e) => println!("{}", e),
+                        }
+                    }
+                },
+                Event::KeyDown {keycode: Some(Keycode::T), ..} => {
+                    test_brush = !test_brush;
+                },
+                Event::KeyDown {keycode: Some(Keycode::P), ..} => {
+                    pinned_brush = !pinned_brush;
+                },
+                Event::KeyDown {keycode: Some(Keycode::Q), ..} => {
+                    selected_charge = if selected_charge == 0.0 { 1.0 } else if selected_charge > 0.0 { -1.0 } else { 0.0 };
+                },
+                Event::KeyDown {keycode: Some(Keycode::G), ..} => {
+                    system.relativity = system.relativity.next();
+                },
+                Event::KeyDown {keycode: Some(Keycode::I), ..} => {
+                    let integrator = system.integrator.next();
+                    system.set_integrator(integrator, total_time - sim_mult);
+                },
+                Event::KeyDown {keycode: Some(Keycode::O), ..} => {
+                    // Off -> Laplace spheres -> Hill spheres -> off
+                    if !show_influence {
+                        show_influence = true;
+                        system.influence = hierarchy::Influence::Laplace;
+                    } else if system.influence == hierarchy::Influence::Laplace {
+                        system.influence = system.influence.next();
+                    } else {
+                        show_influence = false;
+                        system.influence = hierarchy::Influence::Laplace;
+                    }
+                },
+                Event::KeyDown {keycode: Some(Keycode::E), ..} if sdl_context.keyboard().mod_state().intersects(LSHIFTMOD | RSHIFTMOD) => {
+                    system.detect_binaries = !system.detect_binaries;
+                },
+                Event::KeyDown {keycode: Some(Keycode::E), ..} => {
+                    // Off -> detect -> remove -> off. Without a radius from the scenario,
+                    // bodies count as gone once they are well outside the current view.
+                    system.escape = if !system.escape.is_enabled() {
+                        Escape::new(draw_size.0.max(draw_size.1) as f64 / cam.zoom, false)
+                    } else if !system.escape.remove {
+                        Escape::new(system.escape.radius, true)
+                    } else {
+                        Escape::new(0.0, false)
+                    };
+                },
+                Event::KeyDown {keycode: Some(Keycode::Y), ..} => {
+                    system.roche.disruption = system.roche.disruption.next();
+                },
+                Event::KeyDown {keycode: Some(Keycode::J), ..} => {
+                    show_lagrange = !show_lagrange;
+                },
+                Event::KeyDown {keycode: Some(Keycode::B), ..} => {
+                    if let Some(index) = selected_body.and_then(|id| system.index_of(id)) {
+                        let partner = selected_partner.and_then(|id| system.index_of(id)).or_else(|| system.hierarchy().parents[index]);
+                        if let Some(partner) = partner {
+                            system.spawn_at_lagrange_points(index, partner);
+                        }
+                    }
+                },
+                Event::KeyDown {keycode: Some(Keycode::F), ..} => {
+                    match system.mergers.export("mergers", &system.units) {
+                        Ok(()) => println!("Wrote {} mergers to mergers.dot and mergers.csv", system.mergers.mergers.len()),
+                        Err(e) => println!("Failed to export mergers: {}", e),
+                    }
+                },
+                Event::KeyDown {keycode: Some(Keycode::U), ..} => {
+                    let units = system.units.next();
+                    system.set_units(units);
+                },
+                Event::MouseButtonDown {mouse_btn: MouseButton::Right, x, y, ..} => {
+                    // Select the body under the cursor, allowing a few pixels for small ones.
+                    // With shift held it becomes the partner of the selected body instead.
+                    let point = cam.reverse_transform((x as f64 * res_mult, y as f64 * res_mult));
+                    let picked = system.bodies.iter()
+                        .map(|body| (body.id, ((body.x - point.0).powi(2) + (body.y - point.1).powi(2)).sqrt() * cam.zoom - body.size * cam.zoom))
+                        .filter(|&(_, gap)| gap < 10.0 * res_mult)
+                        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                        .map(|(id, _)| id);
+                    if sdl_context.keyboard().mod_state().intersects(LSHIFTMOD | RSHIFTMOD) {
+                        selected_partner = picked.filter(|&id| Some(id) != selected_body);
+                    } else {
+                        selected_body = picked;
+                        selected_partner = None;
+                    }
                 },
                 Event::MouseWheel {y: y_pos, ..} => {
-                    let delta_raw = 0.01 * time_mult * y_pos as f32;
+                    let delta_raw = 0.01 * time_mult * y_pos as f64;
                     raw_zoom += delta_raw;
                     let p_zoom = cam.zoom;
-                    cam.zoom = 2f32.powf(raw_zoom);
+                    cam.zoom = 2f64.powf(raw_zoom);
                     let delta_zoom = cam.zoom - p_zoom;
                     let focus_point = cam.reverse_transform((mouse_x, mouse_y));
                     //cam.zoom += delta_zoom;
@@ -107,6 +269,17 @@ fn main() {
                 },
                 Event::KeyDown {keycode: Some(Keycode::H), ..} => {
                     show_hud = !show_hud;
+                },
+                Event::KeyDown {keycode: Some(Keycode::K), ..} => {
+                    system.softening = system.softening.next(softening_length);
+                },
+                Event::KeyDown {keycode: Some(Keycode::LeftBracket), ..} => {
+                    softening_length = (softening_length / 1.5).max(0.01);
+                    system.softening = system.softening.with_length(softening_length);
+                },
+                Event::KeyDown {keycode: Some(Keycode::RightBracket), ..} => {
+                    softening_length *= 1.5;
+                    system.softening = system.softening.with_length(softening_length);
                 }
                 _ => {}
             }
@@ -116,8 +289,8 @@ fn main() {
         let mouse_state = MouseState::new(&event_pump);
 
 
-        mouse_x = mouse_state.x() as f32 * res_mult;
-        mouse_y = mouse_state.y() as f32 * res_mult;
+        mouse_x = mouse_state.x() as f64 * res_mult;
+        mouse_y = mouse_state.y() as f64 * res_mult;
 
         if !pos_selected {
             selected_pos = cam.reverse_transform((mouse_x, mouse_y));
@@ -135,10 +308,20 @@ fn main() {
             if mouse_pressed && pmouse_pressed {
                 let point1 = selected_pos;
                 let point2 = cam.reverse_transform((mouse_x, mouse_y));
-                selected_vel = ((point2.0 - point1.0) / 50.0, (point2.1 - point1.1) / 50.0);
+                let drag_time = 50.0 / 400.0 * system.time_scale;
+                selected_vel = ((point2.0 - point1.0) / drag_time, (point2.1 - point1.1) / drag_time);
             } else {
                 pos_selected = false;
-                system.add(selected_pos.0, selected_pos.1, selected_vel.0, selected_vel.1, selected_density, selected_size / cam.zoom);
+                let mut body = match selected_mass {
+                    _ if test_brush => Body::test_particle(selected_pos.0, selected_pos.1, selected_vel.0, selected_vel.1, selected_size / cam.zoom),
+                    Some(mass) => Body::with_mass(selected_pos.0, selected_pos.1, selected_vel.0, selected_vel.1, mass, selected_size / cam.zoom),
+                    None => Body::new(selected_pos.0, selected_pos.1, selected_vel.0, selected_vel.1, selected_density, selected_size / cam.zoom),
+                };
+                body.charge = selected_charge;
+                if pinned_brush && !test_brush {
+                    body.motion = motion::Motion::Pinned;
+                }
+                system.add_body(body);
             }
         }
 
@@ -173,22 +356,59 @@ fn main() {
             if selected_density < 1.0 {
                 selected_density = 1.0;
             }
+            selected_mass = None;
         }
         if key_state.is_scancode_pressed(Scancode::C) {
             selected_density -= density_speed * time_mult;
             if selected_density < 1.0 {
                 selected_density = 1.0;
             }
+            selected_mass = None;
+        }
+        // Setting a mass directly decouples it from the brush size and density
+        if key_state.is_scancode_pressed(Scancode::M) || key_state.is_scancode_pressed(Scancode::N) {
+            let brush_radius = selected_size / cam.zoom;
+            let mass = selected_mass.unwrap_or(Body::new(0.0, 0.0, 0.0, 0.0, selected_density, brush_radius).mass);
+            let factor = if key_state.is_scancode_pressed(Scancode::M) { 1.0 + mass_speed } else { 1.0 / (1.0 + mass_speed) };
+            selected_mass = Some(mass * factor.powf(time_mult));
         }
 
-        system.update(&time_mult, &total_time);
+        system.update(&sim_mult, &total_time);
+        for event in system.events.drain() {
+            match event {
+                BodyEvent::Escaped(_) => ejections += 1,
+                // The selection follows a body into whatever absorbs it
+                BodyEvent::Merged { survivor, absorbed } => {
+                    if selected_body == Some(absorbed) {
+                        selected_body = Some(survivor);
+                    }
+                    if selected_partner == Some(absorbed) {
+                        selected_partner = Some(survivor);
+                    }
+                },
+                _ => {},
+            }
+            // Additions and removals only come with edits or other events
+            match event {
+                BodyEvent::BodyAdded(_) | BodyEvent::Removed(_) => {},
+                _ => last_event = format!("{} ({})", event.describe(&system.units), system.units.format_time(total_time)),
+            }
+        }
+        let selected_index = selected_body.and_then(|id| system.index_of(id));
+        if selected_index.is_none() {
+            selected_body = None;
+        }
+        let partner_index = selected_partner.and_then(|id| system.index_of(id)).filter(|&i| Some(i) != selected_index);
+        if partner_index.is_none() {
+            selected_partner = None;
+        }
         canvas.set_draw_color(Color::RGB(0, 0, 0));
         canvas.clear();
 
-        let color_g = if selected_density > 255f32 {
+        let color_g = if selected_density > 255f64 {
             0 as u8
         } else {
-            (255f32 - selected_density) as u8
+            (255f64 - selected_density) as u8
         };
 
         let selected_transformed = cam.transform(selected_pos);
@@ -202,12 +422,155 @@ fn main() {
         }
         
         system.render(&mut canvas, &cam);
+        let hierarchy = if show_influence || selected_index.is_some() { Some(system.hierarchy()) } else { None };
+        if let Some(ref hierarchy) = hierarchy {
+            if show_influence {
+                system.render_influence(&mut canvas, &cam, hierarchy);
+            }
+        }
+        if let (Some(index), Some(ref hierarchy)) = (selected_index, &hierarchy) {
+            if let Some(parent) = hierarchy.parents[index] {
+                system.render_orbit(&mut canvas, &cam, index, parent);
+            }
+            // Where the selected body can go in the restricted problem
+            if system.frame.is_enabled() && system.bodies[index].motion.is_free() {
+                system.render_zero_velocity_curves(&mut canvas, &cam, system.jacobi_constant(index));
+            }
+            if show_lagrange {
+                if let Some(partner) = partner_index.or(hierarchy.parents[index]) {
+                    system.render_lagrange_points(&mut canvas, &cam, index, partner);
+                }
+            }
+            let body = &system.bodies[index];
+            let t = cam.transform((body.x, body.y));
+            let radius = (body.size * cam.zoom + 6.0 * res_mult) as i16;
+            canvas.ellipse_rgba(Point::new(t.0 as i32, t.1 as i32), radius, radius, Color::RGBA(255, 255, 255, 150), 0).expect("Failed to draw selection");
+        }
+        if let Some(partner) = partner_index {
+            let body = &system.bodies[partner];
+            let t = cam.transform((body.x, body.y));
+            let radius = (body.size * cam.zoom + 6.0 * res_mult) as i16;
+            canvas.ellipse_rgba(Point::new(t.0 as i32, t.1 as i32), radius, radius, Color::RGBA(255, 255, 255, 70), 0).expect("Failed to draw selection");
+        }
 
         // Render Fonts
         if show_hud {
-            font.draw_multiline(&mut canvas, format!("R: RESET\nH: TOGGLE HUD\nSCROLL: ZOOM\nZ/X: CHANGE SIZE\nC/V: CHANGE DENSITY").as_str(), 10 * res_mult as i32, 10 * res_mult as i32, false, 20 * res_mult as i32);
+            let help = "R: RESET\nH: TOGGLE HUD\nSCROLL: ZOOM\nZ/X: CHANGE SIZE\nC/V: CHANGE DENSITY\nN/M: CHANGE MASS\nT: TEST PARTICLES\nP: PINNED\nQ: CHARGE\nG: RELATIVITY\nI: INTEGRATOR\nK: SOFTENING\n[/]: SOFTENING LENGTH\nU: UNITS\nL: RELOAD SCENARIO\nO: SPHERES OF INFLUENCE\nE: ESCAPES\nSHIFT E: BINARIES\nJ: LAGRANGE POINTS\nY: TIDAL DISRUPTION\nF: EXPORT MERGERS\nB: FILL LAGRANGE POINTS\nRIGHT CLICK: SELECT\nSHIFT RIGHT CLICK: PARTNER";
+            font.draw_multiline(&mut canvas, help, 10 * res_mult as i32, 10 * res_mult as i32, false, 20 * res_mult as i32);
             font.draw(&mut canvas, format!("{} FPS", fps).as_str(), 10 * res_mult as i32, 10 * res_mult as i32, true);
+            font.draw(&mut canvas, format!("SOFTENING: {} {:.2}", system.softening.name(), system.softening.length()).as_str(), 10 * res_mult as i32, 30 * res_mult as i32, true);
+            font.draw(&mut canvas, format!("FORCE: {}", system.force_law.name()).as_str(), 10 * res_mult as i32, 50 * res_mult as i32, true);
+            font.draw(&mut canvas, format!("RELATIVITY: {}", system.relativity.name()).as_str(), 10 * res_mult as i32, 70 * res_mult as i32, true);
+            let substeps = match system.integrator {
+                Integrator::DormandPrince(adaptive) if adaptive.forced => format!(" ({} STEPS, BEHIND)", adaptive.substeps),
+                Integrator::DormandPrince(adaptive) => format!(" ({} STEPS)", adaptive.substeps),
+                _ => String::new(),
+            };
+            font.draw(&mut canvas, format!("INTEGRATOR: {}{}", system.integrator.name(), substeps).as_str(), 10 * res_mult as i32, 90 * res_mult as i32, true);
+            let regularization = if system.regularization.is_enabled() {
+                format!("{} ({} PAIRS)", system.units.format_length(system.regularization.radius), system.close_pairs.len())
+            } else {
+                String::from("OFF")
+            };
+            font.draw(&mut canvas, format!("REGULARIZATION: {}", regularization).as_str(), 10 * res_mult as i32, 110 * res_mult as i32, true);
+            let escape = if system.escape.is_enabled() {
+                format!("{} BEYOND {}", system.escape.name(), system.units.format_length(system.escape.radius))
+            } else {
+                String::from(system.escape.name())
+            };
+            font.draw(&mut canvas, format!("ESCAPES: {} ({} EJECTED)", escape, ejections).as_str(), 10 * res_mult as i32, 130 * res_mult as i32, true);
+            let frame = if system.frame.is_enabled() {
+                format!("ROTATING AT {:.4e} RAD/{}", system.frame.omega, system.units.time_unit().to_uppercase())
+            } else {
+                String::from("INERTIAL")
+            };
+            font.draw(&mut canvas, format!("FRAME: {}", frame).as_str(), 10 * res_mult as i32, 170 * res_mult as i32, true);
+            let impacts = if system.fragmentation.is_enabled() {
+                format!("SHATTER ABOVE {}", system.units.format_specific_energy(system.fragmentation.threshold))
+            } else {
+                String::from("MERGE")
+            };
+            font.draw(&mut canvas, format!("IMPACTS: {}", impacts).as_str(), 10 * res_mult as i32, 190 * res_mult as i32, true);
+            let tides = if system.roche.is_enabled() {
+                format!("{} ({} PIECES)", system.roche.disruption.name(), system.roche.count)
+            } else {
+                String::from("OFF")
+            };
+            font.draw(&mut canvas, format!("TIDAL DISRUPTION: {}", tides).as_str(), 10 * res_mult as i32, 210 * res_mult as i32, true);
+            let binaries = if system.detect_binaries { system.binaries.len().to_string() } else { String::from("OFF") };
+            font.draw(&mut canvas, format!("BINARIES: {} MERGERS: {}{}", binaries, system.mergers.mergers.len(), if last_event.is_empty() { String::new() } else { format!(" LAST: {}", last_event) }).as_str(), 10 * res_mult as i32, 150 * res_mult as i32, true);
+
+            let units = system.units;
+            let brush_radius = selected_size / cam.zoom;
+            let brush_mass = if test_brush { 0.0 } else { selected_mass.unwrap_or((4.0 / 3.0) * PI * brush_radius.powi(3) * selected_density) };
+            let brush_speed = (selected_vel.0.powi(2) + selected_vel.1.powi(2)).sqrt();
+            font.draw_multiline(&mut canvas, format!("UNITS: {}\nG: {:.4e}\nTIME: {}\nBRUSH: {}\nRADIUS: {}\nMASS: {}\nVELOCITY: {}\nCHARGE: {}",
+                units.name(),
+                system.gravity_const,
+                units.format_time(total_time),
+                if test_brush { "TEST PARTICLE" } else if pinned_brush { "PINNED" } else { "MASSIVE" },
+                units.format_length(brush_radius),
+                units.format_mass(brush_mass) + if selected_mass.is_some() { " (FIXED)" } else { "" },
+                units.format_velocity(brush_speed),
+                selected_charge).as_str(), 10 * res_mult as i32, 230 * res_mult as i32, true, 20 * res_mult as i32);
+
+            // Place of the selected body in the hierarchy and its orbit around its parent
+            if let (Some(index), Some(ref hierarchy)) = (selected_index, &hierarchy) {
+                let mut chain: Vec<String> = hierarchy.ancestors(index).iter().rev().map(|&i| format!("#{}", system.bodies[i].id)).collect();
+                chain.push(format!("#{}", system.bodies[index].id));
+                let radius = hierarchy.radii[index];
+                let mut header = format!("SELECTED: #{}\nHIERARCHY: {}\nSATELLITES: {}\nSOI ({}): {}",
+                    system.bodies[index].id,
+                    chain.join(" > "),
+                    hierarchy.children(index).len(),
+                    system.influence.name(),
+                    if radius.is_infinite() { String::from("UNBOUNDED") } else { units.format_length(radius) });
+                if let Some(partner) = selected_partner.and_then(|id| system.body(id)) {
+                    header += format!("\nPARTNER: #{} ({})", partner.id, units.format_mass(partner.mass)).as_str();
+                }
+                let absorbed = system.mergers.absorbed_by(system.bodies[index].id);
+                if !absorbed.is_empty() {
+                    header += format!("\nABSORBED: {}", absorbed.iter().map(|id| format!("#{}", id)).collect::<Vec<String>>().join(" ")).as_str();
+                }
+                let spin = system.bodies[index].spin;
+                header += format!("\nSPIN: {:.4e} RAD/{}{}", spin, units.time_unit().to_uppercase(),
+                    if spin != 0.0 { format!(" ({})", units.format_time(2.0 * PI / spin.abs())) } else { String::new() }).as_str();
+                header += format!("\nTEMPERATURE: {:.0} K", system.bodies[index].temperature).as_str();
+                if system.frame.is_enabled() {
+                    header += format!("\nJACOBI: {:.6}", system.jacobi_constant(index)).as_str();
+                }
+                font.draw_multiline(&mut canvas, header.as_str(), 10 * res_mult as i32, 410 * res_mult as i32, true, 20 * res_mult as i32);
+
+                // The orbit goes below the key list, which leaves the header room to grow
+                if let Some(primary) = hierarchy.parents[index] {
+                    let elements = system.elements_of(index, primary);
+                    let text = format!("ORBIT AROUND #{}\nA: {}\nE: {:.4}\nDIRECTION: {}\nPERIAPSIS ARG: {:.1} DEG\nTRUE ANOMALY: {:.1} DEG\nPERIOD: {}",
+                        system.bodies[primary].id,
+                        units.format_length(elements.semi_major),
+                        elements.eccentricity,
+                        if elements.retrograde { "RETROGRADE" } else { "PROGRADE" },
+                        elements.periapsis_arg.to_degrees(),
+                        elements.true_anomaly.to_degrees(),
+                        if elements.is_bound() { units.format_time(elements.period) } else { String::from("UNBOUND") });
+                    let top = 10 + 20 * (help.lines().count() + 1) as i32;
+                    font.draw_multiline(&mut canvas, text.as_str(), 10 * res_mult as i32, top * res_mult as i32, false, 20 * res_mult as i32);
+                }
+            }
         }
         canvas.present();
     }
 }
+
+fn apply_scenario(scenario: Scenario, system: &mut system::System, cam: &mut cam::Cam, draw_size: (u32, u32)) -> f64 {
+    *system = scenario.system;
+    let (center_x, center_y, zoom) = scenario.camera.unwrap_or((0.0, 0.0, 1.0));
+    cam.zoom = zoom;
+    cam.x = center_x * zoom - draw_size.0 as f64 / 2.0;
+    cam.y = center_y * zoom - draw_size.1 as f64 / 2.0;
+    zoom.log2()
+}
+
+// Prints the events of the system to stdout as they happen
+fn subscribe_logger(system: &mut system::System) {
+    system.events.subscribe(|event, units| println!("{}", event.describe(units)));
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use body::BodyId;
+use units::UnitSystem;
+
+// A merge of two massive bodies. The product keeps the ID of the survivor, and loses
+// `ejected_mass` to fragments when the impact shattered it.
+#[derive(Copy, Clone)]
+pub struct Merger {
+    pub time: f64,
+    pub survivor: BodyId,
+    pub absorbed: BodyId,
+    pub survivor_mass: f64,
+    pub absorbed_mass: f64,
+    pub ejected_mass: f64,
+    pub impact_speed: f64,
+}
+
+impl Merger {
+    pub fn product_mass(&self) -> f64 {
+        self.survivor_mass + self.absorbed_mass - self.ejected_mass
+    }
+}
+
+// Every merge of massive bodies in the order they happened, from which the tree of how
+// each body was assembled follows. Test particles that are swept up don't add mass and
+// are left out.
+pub struct MergerTree {
+    pub mergers: Vec<Merger>,
+}
+
+impl MergerTree {
+    pub fn new() -> MergerTree {
+        MergerTree {
+            mergers: vec![],
+        }
+    }
+
+    pub fn record(&mut self, merger: Merger) {
+        self.mergers.push(merger);
+    }
+
+    // IDs of the bodies merged into `id` over its lifetime, in order
+    pub fn absorbed_by(&self, id: BodyId) -> Vec<BodyId> {
+        self.mergers.iter().filter(|merger| merger.survivor == id).map(|merger| merger.absorbed).collect()
+    }
+
+    // Graphviz digraph of the tree. As IDs outlive merges, each node is a body between
+    // two of its merges, named `<id>.<merges so far>`, with edges from both bodies that
+    // went into a merge to its product.
+    pub fn to_dot(&self, units: &UnitSystem) -> String {
+        let mut merges: HashMap<BodyId, usize> = HashMap::new();
+        let mut dot = String::from("digraph mergers {\n    rankdir=LR;\n    node [shape=box];\n");
+        for merger in &self.mergers {
+            let survivor = *merges.get(&merger.survivor).unwrap_or(&0);
+            let absorbed = *merges.get(&merger.absorbed).unwrap_or(&0);
+            // Bodies are only declared before their first merge, later versions are
+            // declared as products
+            for &(id, count, mass) in &[(merger.survivor, survivor, merger.survivor_mass), (merger.absorbed, absorbed, merger.absorbed_mass)] {
+                if count == 0 {
+                    dot += &format!("    \"{}.0\" [label=\"#{}\\n{}\"];\n", id, id, units.format_mass(mass));
+                }
+            }
+            dot += &format!("    \"{}.{}\" [label=\"#{}\\n{}\\nt = {}\"];\n",
+                merger.survivor, survivor + 1, merger.survivor, units.format_mass(merger.product_mass()), units.format_time(merger.time));
+            dot += &format!("    \"{}.{}\" -> \"{}.{}\";\n", merger.survivor, survivor, merger.survivor, survivor + 1);
+            dot += &format!("    \"{}.{}\" -> \"{}.{}\" [label=\"{}\"];\n",
+                merger.absorbed, absorbed, merger.survivor, survivor + 1, units.format_velocity(merger.impact_speed));
+            merges.insert(merger.survivor, survivor + 1);
+        }
+        dot + "}\n"
+    }
+
+    // One row per merge, with values in `units` as named in the header. The product is
+    // the node of the survivor after the merge in `to_dot`.
+    pub fn to_csv(&self, units: &UnitSystem) -> String {
+        let (length, mass, time) = (units.length_unit(), units.mass_unit(), units.time_unit());
+        let mut merges: HashMap<BodyId, usize> = HashMap::new();
+        let mut csv = format!("time [{}],product,survivor,absorbed,survivor_mass [{}],absorbed_mass [{}],ejected_mass [{}],product_mass [{}],impact_speed [{}/{}]\n",
+            time, mass, mass, mass, mass, length, time);
+        for merger in &self.mergers {
+            let product = *merges.get(&merger.survivor).unwrap_or(&0) + 1;
+            csv += &format!("{},{}.{},{},{},{},{},{},{},{}\n",
+                merger.time,
+                merger.survivor,
+                product,
+                merger.survivor,
+                merger.absorbed,
+                merger.survivor_mass,
+                merger.absorbed_mass,
+                merger.ejected_mass,
+                merger.product_mass(),
+                merger.impact_speed);
+            merges.insert(merger.survivor, product);
+        }
+        csv
+    }
+
+    // Writes `<path>.dot` and `<path>.csv`
+    pub fn export(&self, path: &str, units: &UnitSystem) -> io::Result<()> {
+        File::create(format!("{}.dot", path))?.write_all(self.to_dot(units).as_bytes())?;
+        File::create(format!("{}.csv", path))?.write_all(self.to_csv(units).as_bytes())
+    }
+}
@@ -0,0 +1,51 @@
+use orbit::Orbit;
+
+// How a body moves. Bodies that aren't free still exert gravity but ignore the forces
+// acting on them.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Motion {
+    Free,
+    Pinned,
+    Kepler(Orbit),
+    // Follows the keyframed path with this index in `System::paths`
+    Scripted(usize),
+}
+
+impl Motion {
+    pub fn is_free(&self) -> bool {
+        *self == Motion::Free
+    }
+}
+
+// Keyframes of (time, x, y), linearly interpolated
+pub struct Path {
+    pub keyframes: Vec<(f64, f64, f64)>,
+    pub looped: bool,
+}
+
+impl Path {
+    pub fn state_at(&self, time: f64) -> ((f64, f64), (f64, f64)) {
+        let first = self.keyframes[0];
+        let last = self.keyframes[self.keyframes.len() - 1];
+        let duration = last.0 - first.0;
+
+        let mut time = time;
+        if self.looped && duration > 0.0 {
+            time = first.0 + (time - first.0).rem_euclid(duration);
+        }
+        if time <= first.0 {
+            return ((first.1, first.2), (0.0, 0.0));
+        }
+
+        for pair in self.keyframes.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            if time <= end.0 {
+                let span = end.0 - start.0;
+                let t = if span > 0.0 { (time - start.0) / span } else { 1.0 };
+                let velocity = if span > 0.0 { ((end.1 - start.1) / span, (end.2 - start.2) / span) } else { (0.0, 0.0) };
+                return ((start.1 + (end.1 - start.1) * t, start.2 + (end.2 - start.2) * t), velocity);
+            }
+        }
+        ((last.1, last.2), (0.0, 0.0))
+    }
+}
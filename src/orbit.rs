@@ -0,0 +1,284 @@
+// A fixed elliptical Kepler orbit around a focus that does not move
+#[derive(Copy, Clone, PartialEq)]
+pub struct Orbit {
+    pub focus_x: f64,
+    pub focus_y: f64,
+    pub semi_major: f64,
+    pub eccentricity: f64,
+    pub periapsis_arg: f64,
+    pub period: f64,
+    // Time of periapsis passage
+    pub epoch: f64,
+}
+
+impl Orbit {
+    pub fn state_at(&self, time: f64) -> ((f64, f64), (f64, f64)) {
+        let e = self.eccentricity;
+        let mean_motion = 2.0 * ::PI / self.period;
+        let anomaly = solve_kepler(mean_motion * (time - self.epoch), e);
+        let semi_minor = self.semi_major * (1.0 - e.powi(2)).sqrt();
+        let anomaly_rate = mean_motion / (1.0 - e * anomaly.cos());
+
+        let position = (self.semi_major * (anomaly.cos() - e), semi_minor * anomaly.sin());
+        let velocity = (-self.semi_major * anomaly.sin() * anomaly_rate, semi_minor * anomaly.cos() * anomaly_rate);
+
+        let (sin_w, cos_w) = self.periapsis_arg.sin_cos();
+        let rotate = |v: (f64, f64)| (v.0 * cos_w - v.1 * sin_w, v.0 * sin_w + v.1 * cos_w);
+        let position = rotate(position);
+        ((self.focus_x + position.0, self.focus_y + position.1), rotate(velocity))
+    }
+}
+
+// Osculating orbital elements of a body relative to a primary. In the plane the
+// inclination reduces to the sense of revolution, so retrograde orbits run clockwise in
+// simulation coordinates. Unbound orbits have a negative semi-major axis and an
+// infinite period.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Elements {
+    pub semi_major: f64,
+    pub eccentricity: f64,
+    pub retrograde: bool,
+    pub periapsis_arg: f64,
+    pub true_anomaly: f64,
+    pub period: f64,
+}
+
+impl Elements {
+    // Elements of the relative state of a body, with `mu` = G times the combined mass
+    pub fn from_state(position: (f64, f64), velocity: (f64, f64), mu: f64) -> Elements {
+        let distance = (position.0.powi(2) + position.1.powi(2)).sqrt();
+        let v2 = velocity.0.powi(2) + velocity.1.powi(2);
+        let radial_velocity = position.0 * velocity.0 + position.1 * velocity.1;
+        let angular_momentum = position.0 * velocity.1 - position.1 * velocity.0;
+        let energy = 0.5 * v2 - mu / distance;
+
+        // Eccentricity vector, pointing at periapsis
+        let e_x = ((v2 - mu / distance) * position.0 - radial_velocity * velocity.0) / mu;
+        let e_y = ((v2 - mu / distance) * position.1 - radial_velocity * velocity.1) / mu;
+        let eccentricity = (e_x.powi(2) + e_y.powi(2)).sqrt();
+
+        let retrograde = angular_momentum < 0.0;
+        // Circular orbits measure the anomaly from the x axis
+        let periapsis_arg = if eccentricity > 1e-12 { e_y.atan2(e_x) } else { 0.0 };
+        let angle = position.1.atan2(position.0) - periapsis_arg;
+        let true_anomaly = normalize_angle(if retrograde { -angle } else { angle });
+
+        let semi_major = -mu / (2.0 * energy);
+        let period = if semi_major > 0.0 { 2.0 * ::PI * (semi_major.powi(3) / mu).sqrt() } else { f64::INFINITY };
+
+        Elements {
+            semi_major,
+            eccentricity,
+            retrograde,
+            periapsis_arg,
+            true_anomaly,
+            period,
+        }
+    }
+
+    // Relative position and velocity, with `mu` = G times the combined mass
+    pub fn to_state(self, mu: f64) -> ((f64, f64), (f64, f64)) {
+        let e = self.eccentricity;
+        let semi_latus = self.semi_major * (1.0 - e.powi(2));
+        let (sin_v, cos_v) = self.true_anomaly.sin_cos();
+        let distance = semi_latus / (1.0 + e * cos_v);
+        let speed = (mu / semi_latus).sqrt();
+
+        // In the perifocal frame, mirrored for retrograde orbits
+        let side = if self.retrograde { -1.0 } else { 1.0 };
+        let position = (distance * cos_v, side * distance * sin_v);
+        let velocity = (-speed * sin_v, side * speed * (e + cos_v));
+
+        let (sin_w, cos_w) = self.periapsis_arg.sin_cos();
+        let rotate = |v: (f64, f64)| (v.0 * cos_w - v.1 * sin_w, v.0 * sin_w + v.1 * cos_w);
+        (rotate(position), rotate(velocity))
+    }
+
+    pub fn is_bound(&self) -> bool {
+        self.eccentricity < 1.0
+    }
+
+    // Distance of closest approach
+    pub fn periapsis(&self) -> f64 {
+        self.semi_major * (1.0 - self.eccentricity)
+    }
+
+    // Greatest distance of a bound orbit
+    pub fn apoapsis(&self) -> f64 {
+        self.semi_major * (1.0 + self.eccentricity)
+    }
+}
+
+// Wraps an angle into (-pi, pi]
+pub fn normalize_angle(angle: f64) -> f64 {
+    let wrapped = (angle + ::PI) % (2.0 * ::PI);
+    if wrapped <= 0.0 {
+        wrapped + ::PI
+    } else {
+        wrapped - ::PI
+    }
+}
+
+// Solves Kepler's equation `E - e sin E = M` for the eccentric anomaly of an ellipse,
+// in [0, 2 pi). The mean anomaly only grows with it, so Newton's method is kept inside a
+// bracket and falls back to bisection near periapsis of very eccentric orbits.
+pub fn solve_kepler(mean_anomaly: f64, eccentricity: f64) -> f64 {
+    let mean_anomaly = mean_anomaly.rem_euclid(2.0 * ::PI);
+    let mut low = 0.0;
+    let mut high = 2.0 * ::PI;
+    let mut anomaly = if eccentricity > 0.8 { ::PI } else { mean_anomaly };
+    for _ in 0..100 {
+        let f = anomaly - eccentricity * anomaly.sin() - mean_anomaly;
+        if f > 0.0 {
+            high = anomaly;
+        } else {
+            low = anomaly;
+        }
+        let mut next = anomaly - f / (1.0 - eccentricity * anomaly.cos());
+        if !(next > low && next < high) {
+            next = 0.5 * (low + high);
+        }
+        let converged = (next - anomaly).abs() < 1e-12;
+        anomaly = next;
+        if converged {
+            break;
+        }
+    }
+    anomaly
+}
+
+// Advances a body on an unperturbed two-body orbit with gravitational parameter `mu` by
+// `dt`, using universal variables so that any conic section is handled
+pub fn kepler_drift(position: (f64, f64), velocity: (f64, f64), mu: f64, dt: f64) -> ((f64, f64), (f64, f64)) {
+    let r0 = (position.0.powi(2) + position.1.powi(2)).sqrt();
+    if r0 <= 0.0 || mu <= 0.0 {
+        return ((position.0 + velocity.0 * dt, position.1 + velocity.1 * dt), velocity);
+    }
+    // Going back in time is going forward with the velocity reversed
+    if dt < 0.0 {
+        let (new_position, new_velocity) = kepler_drift(position, (-velocity.0, -velocity.1), mu, -dt);
+        return (new_position, (-new_velocity.0, -new_velocity.1));
+    }
+    let v2 = velocity.0.powi(2) + velocity.1.powi(2);
+    let radial_velocity = (position.0 * velocity.0 + position.1 * velocity.1) / r0;
+    let sqrt_mu = mu.sqrt();
+    // Reciprocal of the semi-major axis
+    let alpha = 2.0 / r0 - v2 / mu;
+
+    // Whole periods of a bound orbit can be skipped
+    let mut dt = dt;
+    if alpha > 0.0 {
+        let period = 2.0 * ::PI / (sqrt_mu * alpha.powf(1.5));
+        dt %= period;
+    }
+
+    // Kepler's equation in the universal anomaly, with its derivative r
+    let kepler = |chi: f64| {
+        let z = alpha * chi.powi(2);
+        let (c, s) = stumpff(z);
+        let f = r0 * radial_velocity / sqrt_mu * chi.powi(2) * c + (1.0 - alpha * r0) * chi.powi(3) * s + r0 * chi - sqrt_mu * dt;
+        let df = r0 * radial_velocity / sqrt_mu * chi * (1.0 - z * s) + (1.0 - alpha * r0) * chi.powi(2) * c + r0;
+        (f, df)
+    };
+
+    // The time since the start only grows with the anomaly, so Newton's method can be kept
+    // inside a bracket and fall back to bisection when it would leave it
+    let mut low = 0.0;
+    let mut high = sqrt_mu * dt / r0;
+    while kepler(high).0 < 0.0 {
+        low = high;
+        high *= 2.0;
+    }
+    let mut chi = high;
+    for _ in 0..100 {
+        let (f, df) = kepler(chi);
+        if f > 0.0 {
+            high = chi;
+        } else {
+            low = chi;
+        }
+        let mut next = chi - f / df;
+        if !(next > low && next < high) {
+            next = 0.5 * (low + high);
+        }
+        let converged = (next - chi).abs() <= 1e-13 * chi.abs();
+        chi = next;
+        if converged {
+            break;
+        }
+    }
+
+    let z = alpha * chi.powi(2);
+    let (c, s) = stumpff(z);
+    let f = 1.0 - chi.powi(2) / r0 * c;
+    let g = dt - chi.powi(3) / sqrt_mu * s;
+    let new_position = (f * position.0 + g * velocity.0, f * position.1 + g * velocity.1);
+    let r = (new_position.0.powi(2) + new_position.1.powi(2)).sqrt();
+    let f_dot = sqrt_mu / (r * r0) * (z * s - 1.0) * chi;
+    let g_dot = 1.0 - chi.powi(2) / r * c;
+    let new_velocity = (f_dot * position.0 + g_dot * velocity.0, f_dot * position.1 + g_dot * velocity.1);
+    (new_position, new_velocity)
+}
+
+// Stumpff functions C(z) and S(z)
+pub fn stumpff(z: f64) -> (f64, f64) {
+    if z.abs() < 1e-3 {
+        (0.5 - z / 24.0 + z.powi(2) / 720.0, 1.0 / 6.0 - z / 120.0 + z.powi(2) / 5040.0)
+    } else if z > 0.0 {
+        let root = z.sqrt();
+        ((1.0 - root.cos()) / z, (root - root.sin()) / root.powi(3))
+    } else {
+        let root = (-z).sqrt();
+        ((root.cosh() - 1.0) / -z, (root.sinh() - root) / root.powi(3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: (f64, f64), b: (f64, f64), tolerance: f64) {
+        assert!((a.0 - b.0).abs() < tolerance && (a.1 - b.1).abs() < tolerance, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn kepler_drift_round_trip() {
+        // Elliptic, nearly parabolic and hyperbolic orbits
+        for &speed in &[0.8, 1.2, 1.41, 2.5] {
+            let (position, velocity) = ((1.0, 0.0), (0.1, speed));
+            let (forward_position, forward_velocity) = kepler_drift(position, velocity, 1.0, 3.7);
+            let (back_position, back_velocity) = kepler_drift(forward_position, forward_velocity, 1.0, -3.7);
+            assert_close(back_position, position, 1e-9);
+            assert_close(back_velocity, velocity, 1e-9);
+        }
+    }
+
+    #[test]
+    fn kepler_drift_conserves_energy() {
+        let (position, velocity) = ((1.0, 0.0), (0.0, 2.0));
+        let (new_position, new_velocity) = kepler_drift(position, velocity, 1.0, 5.0);
+        let energy = |p: (f64, f64), v: (f64, f64)| 0.5 * (v.0.powi(2) + v.1.powi(2)) - 1.0 / (p.0.powi(2) + p.1.powi(2)).sqrt();
+        assert!((energy(new_position, new_velocity) - energy(position, velocity)).abs() < 1e-10);
+    }
+
+    #[test]
+    fn solve_kepler_high_eccentricity() {
+        let e = 0.99;
+        for k in 0..20 {
+            let mean_anomaly = -3.0 + 0.3 * k as f64;
+            let anomaly = solve_kepler(mean_anomaly, e);
+            let residual = anomaly - e * anomaly.sin() - mean_anomaly.rem_euclid(2.0 * ::PI);
+            assert!(residual.abs() < 1e-10, "residual {} at M = {}", residual, mean_anomaly);
+        }
+    }
+
+    #[test]
+    fn elements_round_trip() {
+        for &(position, velocity) in &[((1.0, 0.5), (-0.3, 0.9)), ((0.7, -1.2), (0.8, 0.2)), ((1.0, 0.0), (0.0, -1.8))] {
+            let elements = Elements::from_state(position, velocity, 1.0);
+            let (new_position, new_velocity) = elements.to_state(1.0);
+            assert_close(new_position, position, 1e-10);
+            assert_close(new_velocity, velocity, 1e-10);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+// Static background potentials, evaluated in the plane of the simulation
+#[derive(Copy, Clone, PartialEq)]
+pub enum Potential {
+    Uniform { g_x: f64, g_y: f64 },
+    PointMass { mass: f64 },
+    // Flat rotation curve with circular speed `v0` outside the core radius
+    Logarithmic { v0: f64, core: f64 },
+    // Navarro-Frenk-White halo, `mass` being the characteristic mass 4 pi rho0 rs^3
+    Nfw { mass: f64, scale: f64 },
+    MiyamotoNagai { mass: f64, a: f64, b: f64 },
+}
+
+impl Potential {
+    // Acceleration at an offset from the centre of the potential
+    pub fn acceleration(&self, dx: f64, dy: f64, gravity_const: f64) -> (f64, f64) {
+        let r2 = dx.powi(2) + dy.powi(2);
+        let r = r2.sqrt().max(1e-12);
+
+        // Inward acceleration divided by the distance
+        let scalar = match *self {
+            Potential::Uniform { g_x, g_y } => return (g_x, g_y),
+            Potential::PointMass { mass } => gravity_const * mass / r.powi(3),
+            Potential::Logarithmic { v0, core } => v0.powi(2) / (core.powi(2) + r2),
+            Potential::Nfw { mass, scale } => {
+                let x = r / scale;
+                let enclosed = mass * ((1.0 + x).ln() - x / (1.0 + x));
+                gravity_const * enclosed / r.powi(3)
+            },
+            Potential::MiyamotoNagai { mass, a, b } => {
+                gravity_const * mass / (r2 + (a + b).powi(2)).powf(1.5)
+            },
+        };
+        (-dx * scalar, -dy * scalar)
+    }
+}
+
+// A potential whose centre can drift and whose strength can be grown in over time
+#[derive(Copy, Clone, PartialEq)]
+pub struct ExternalField {
+    pub potential: Potential,
+    pub x: f64,
+    pub y: f64,
+    pub v_x: f64,
+    pub v_y: f64,
+    // Time over which the field ramps up linearly from zero, or 0 to apply it at once
+    pub growth_time: f64,
+}
+
+impl ExternalField {
+    pub fn new(potential: Potential) -> ExternalField {
+        ExternalField {
+            potential,
+            x: 0.0,
+            y: 0.0,
+            v_x: 0.0,
+            v_y: 0.0,
+            growth_time: 0.0,
+        }
+    }
+
+    pub fn acceleration(&self, x: f64, y: f64, time: f64, gravity_const: f64) -> (f64, f64) {
+        let center = (self.x + self.v_x * time, self.y + self.v_y * time);
+        let strength = if self.growth_time > 0.0 { (time / self.growth_time).clamp(0.0, 1.0) } else { 1.0 };
+        let a = self.potential.acceleration(x - center.0, y - center.1, gravity_const);
+        (a.0 * strength, a.1 * strength)
+    }
+}
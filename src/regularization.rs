@@ -0,0 +1,208 @@
+use body::{Body, BodyClass};
+use orbit;
+
+// Regularization of close encounters. Free massive bodies that come closer than `radius`
+// are paired up and the relative motion of each pair is advanced as a Kepler orbit in
+// Levi-Civita coordinates, the planar form of the Kustaanheimo-Stiefel transformation,
+// where it becomes a harmonic oscillator that stays regular however close the approach.
+// Their mutual attraction is left out of the ordinary force computation, so the rest of
+// the system only perturbs a pair through smooth tidal kicks.
+// Pairs are only formed while the tidal acceleration of other bodies stays below this
+// fraction of their mutual attraction
+const MAX_PERTURBATION: f64 = 0.01;
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct Regularization {
+    pub radius: f64,
+}
+
+impl Regularization {
+    pub fn new(radius: f64) -> Regularization {
+        Regularization {
+            radius,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.radius > 0.0
+    }
+
+    // Pairs of free massive bodies that are closer than the radius or will pass within it
+    // during `interval`, closest first, with every body in at most one pair. Pairs whose
+    // relative motion is strongly perturbed by a third body are left to the integrator,
+    // as splitting them off would be inaccurate.
+    pub fn close_pairs(&self, bodies: &[Body], gravity_const: f64, interval: f64) -> Vec<(usize, usize)> {
+        if !self.is_enabled() {
+            return vec![];
+        }
+        let candidates: Vec<usize> = (0..bodies.len())
+            .filter(|&i| bodies[i].class == BodyClass::Massive && bodies[i].mass > 0.0 && bodies[i].motion.is_free())
+            .collect();
+
+        let mut close = vec![];
+        for (index, &i) in candidates.iter().enumerate() {
+            for &j in &candidates[index + 1..] {
+                let (position, velocity) = relative_state(&bodies[i], &bodies[j]);
+                let distance = (position.0.powi(2) + position.1.powi(2)).sqrt();
+                let speed = (velocity.0.powi(2) + velocity.1.powi(2)).sqrt();
+                let mu = gravity_const * (bodies[i].mass + bodies[j].mass);
+                let approaching = distance - speed * interval < self.radius && periapsis(position, velocity, mu) < self.radius;
+                if (distance < self.radius || approaching) && perturbation(bodies, &candidates, i, j) < MAX_PERTURBATION {
+                    close.push((distance, i, j));
+                }
+            }
+        }
+        close.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let mut paired = vec![false; bodies.len()];
+        let mut pairs = vec![];
+        for (_, i, j) in close {
+            if !paired[i] && !paired[j] {
+                paired[i] = true;
+                paired[j] = true;
+                pairs.push((i, j));
+            }
+        }
+        pairs
+    }
+}
+
+// Closest approach of a two-body orbit
+fn periapsis(position: (f64, f64), velocity: (f64, f64), mu: f64) -> f64 {
+    let distance = (position.0.powi(2) + position.1.powi(2)).sqrt();
+    let angular_momentum = position.0 * velocity.1 - position.1 * velocity.0;
+    let energy = 0.5 * (velocity.0.powi(2) + velocity.1.powi(2)) - mu / distance;
+    let eccentricity = (1.0 + 2.0 * energy * angular_momentum.powi(2) / mu.powi(2)).max(0.0).sqrt();
+    angular_momentum.powi(2) / (mu * (1.0 + eccentricity))
+}
+
+// Tidal acceleration of the other massive bodies on a pair relative to their mutual
+// attraction
+fn perturbation(bodies: &[Body], massive: &[usize], i: usize, j: usize) -> f64 {
+    let (first, second) = (&bodies[i], &bodies[j]);
+    let total_mass = first.mass + second.mass;
+    let center = ((first.mass * first.x + second.mass * second.x) / total_mass, (first.mass * first.y + second.mass * second.y) / total_mass);
+    let separation = ((first.x - second.x).powi(2) + (first.y - second.y).powi(2)).sqrt();
+
+    massive.iter().filter(|&&k| k != i && k != j).map(|&k| {
+        let distance = ((bodies[k].x - center.0).powi(2) + (bodies[k].y - center.1).powi(2)).sqrt();
+        2.0 * bodies[k].mass / total_mass * (separation / distance).powi(3)
+    }).sum()
+}
+
+// Unsoftened Newtonian acceleration of `target` towards `source`
+pub fn point_mass_acceleration(target: &Body, source: &Body, gravity_const: f64) -> (f64, f64) {
+    let offset = (source.x - target.x, source.y - target.y);
+    let distance = (offset.0.powi(2) + offset.1.powi(2)).sqrt();
+    if distance <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let acc_scalar = gravity_const * source.mass / distance.powi(3);
+    (offset.0 * acc_scalar, offset.1 * acc_scalar)
+}
+
+// Position and velocity of `first` relative to `second`
+pub fn relative_state(first: &Body, second: &Body) -> ((f64, f64), (f64, f64)) {
+    ((first.x - second.x, first.y - second.y), (first.v_x - second.v_x, first.v_y - second.v_y))
+}
+
+// Places a pair at the given relative state around its unchanged centre of mass
+pub fn set_relative_state(first: &mut Body, second: &mut Body, state: ((f64, f64), (f64, f64))) {
+    let total_mass = first.mass + second.mass;
+    let center = ((first.mass * first.x + second.mass * second.x) / total_mass, (first.mass * first.y + second.mass * second.y) / total_mass);
+    let center_velocity = ((first.mass * first.v_x + second.mass * second.v_x) / total_mass, (first.mass * first.v_y + second.mass * second.v_y) / total_mass);
+    let ((x, y), (v_x, v_y)) = state;
+    let (first_share, second_share) = (second.mass / total_mass, first.mass / total_mass);
+
+    first.set_state(((center.0 + first_share * x, center.1 + first_share * y), (center_velocity.0 + first_share * v_x, center_velocity.1 + first_share * v_y)));
+    second.set_state(((center.0 - second_share * x, center.1 - second_share * y), (center_velocity.0 - second_share * v_x, center_velocity.1 - second_share * v_y)));
+}
+
+// Advances the relative motion of a pair along their two-body orbit by `dt`
+pub fn drift_pair(first: &mut Body, second: &mut Body, gravity_const: f64, dt: f64) {
+    let (position, velocity) = relative_state(first, second);
+    let state = levi_civita_drift(position, velocity, gravity_const * (first.mass + second.mass), dt);
+    set_relative_state(first, second, state);
+}
+
+// Solves the two-body problem with gravitational parameter `mu` over `dt`. With x = u^2
+// for complex u and the fictitious time ds = dt / r, the motion is u'' = (h / 2) u for the
+// conserved energy h, which is solved in closed form. Physical time is recovered from
+// t(s) = integral of |u|^2 ds.
+pub fn levi_civita_drift(position: (f64, f64), velocity: (f64, f64), mu: f64, dt: f64) -> ((f64, f64), (f64, f64)) {
+    let r0 = (position.0.powi(2) + position.1.powi(2)).sqrt();
+    if r0 <= 0.0 || mu <= 0.0 {
+        return ((position.0 + velocity.0 * dt, position.1 + velocity.1 * dt), velocity);
+    }
+    let energy = 0.5 * (velocity.0.powi(2) + velocity.1.powi(2)) - mu / r0;
+    let beta = -0.5 * energy;
+
+    let mut dt = dt;
+    if energy < 0.0 {
+        let semi_major = -mu / (2.0 * energy);
+        dt %= 2.0 * ::PI * (semi_major.powi(3) / mu).sqrt();
+    }
+
+    let angle = 0.5 * position.1.atan2(position.0);
+    let u0 = (r0.sqrt() * angle.cos(), r0.sqrt() * angle.sin());
+    let du0 = multiply((0.5 * u0.0, -0.5 * u0.1), velocity);
+    let u0_squared = u0.0.powi(2) + u0.1.powi(2);
+    let du0_squared = du0.0.powi(2) + du0.1.powi(2);
+    let u0_dot_du0 = u0.0 * du0.0 + u0.1 * du0.1;
+
+    // Coefficients of u0 and u0' in u(s) and u'(s)
+    let coefficients = |s: f64| {
+        let (c, s_z) = orbit::stumpff(beta * s.powi(2));
+        let z = beta * s.powi(2);
+        let cosine = 1.0 - z * c;
+        let sine = s * (1.0 - z * s_z);
+        (cosine, sine, -beta * sine)
+    };
+    let time_at = |s: f64| {
+        let big_z = 4.0 * beta * s.powi(2);
+        let (c, s_z) = orbit::stumpff(big_z);
+        u0_squared * (s - 0.5 * s * big_z * s_z) + 2.0 * u0_dot_du0 * s.powi(2) * c + 2.0 * du0_squared * s.powi(3) * s_z
+    };
+    let radius_at = |s: f64| {
+        let (cosine, sine, _) = coefficients(s);
+        (cosine * u0.0 + sine * du0.0).powi(2) + (cosine * u0.1 + sine * du0.1).powi(2)
+    };
+
+    // Newton's method on t(s) = dt, falling back to bisection when it leaves the bracket
+    let mut low = 0.0;
+    let mut high = dt / r0;
+    while time_at(high) < dt {
+        low = high;
+        high *= 2.0;
+    }
+    let mut s = dt / r0;
+    for _ in 0..100 {
+        let error = time_at(s) - dt;
+        if error > 0.0 {
+            high = s;
+        } else {
+            low = s;
+        }
+        let mut next = s - error / radius_at(s);
+        if !(next > low && next < high) {
+            next = 0.5 * (low + high);
+        }
+        let converged = (next - s).abs() <= 1e-13 * s.abs();
+        s = next;
+        if converged {
+            break;
+        }
+    }
+
+    let (cosine, sine, rate) = coefficients(s);
+    let u = (cosine * u0.0 + sine * du0.0, cosine * u0.1 + sine * du0.1);
+    let du = (rate * u0.0 + cosine * du0.0, rate * u0.1 + cosine * du0.1);
+    let r = u.0.powi(2) + u.1.powi(2);
+    let new_velocity = multiply(u, du);
+    (multiply(u, u), (2.0 * new_velocity.0 / r, 2.0 * new_velocity.1 / r))
+}
+
+// Product of two complex numbers
+fn multiply(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
@@ -0,0 +1,88 @@
+use body::Body;
+
+// Post-Newtonian corrections to the pairwise acceleration. `first_order` adds the 1PN
+// (Einstein-Infeld-Hoffmann) terms responsible for periapsis precession, in harmonic
+// coordinates. `radiation` adds the 2.5PN radiation reaction that makes close binaries
+// spiral in by emitting gravitational waves, in the simpler form it takes in an ADM-type
+// gauge, which loses the same energy and angular momentum as the harmonic one.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Relativity {
+    pub first_order: bool,
+    pub radiation: bool,
+    pub speed_of_light: f64,
+}
+
+impl Relativity {
+    pub fn new(speed_of_light: f64) -> Relativity {
+        Relativity {
+            first_order: false,
+            radiation: false,
+            speed_of_light,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.first_order || self.radiation
+    }
+
+    // Cycles off -> 1PN -> 1PN with radiation reaction
+    pub fn next(&self) -> Relativity {
+        let mut next = *self;
+        match (self.first_order, self.radiation) {
+            (false, _) => next.first_order = true,
+            (true, false) => next.radiation = true,
+            (true, true) => {
+                next.first_order = false;
+                next.radiation = false;
+            },
+        }
+        next
+    }
+
+    pub fn name(&self) -> &'static str {
+        match (self.first_order, self.radiation) {
+            (false, false) => "OFF",
+            (true, false) => "1PN",
+            (false, true) => "GW",
+            (true, true) => "1PN+GW",
+        }
+    }
+
+    // Correction to the acceleration of `target` due to `source`. The relative
+    // acceleration of the pair is shared out by mass, as in the centre of mass frame.
+    pub fn acceleration(&self, target: &Body, source: &Body, gravity_const: f64) -> (f64, f64) {
+        if !self.is_enabled() || source.mass <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let total_mass = target.mass + source.mass;
+
+        let x = (target.x - source.x, target.y - source.y);
+        let v = (target.v_x - source.v_x, target.v_y - source.v_y);
+        let r = (x.0.powi(2) + x.1.powi(2)).sqrt();
+        if r <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let n = (x.0 / r, x.1 / r);
+        let r_dot = n.0 * v.0 + n.1 * v.1;
+        let v2 = v.0.powi(2) + v.1.powi(2);
+        let gm = gravity_const * total_mass;
+        let eta = target.mass * source.mass / total_mass.powi(2);
+        let c2 = self.speed_of_light.powi(2);
+
+        let mut coefficient_n = 0.0;
+        let mut coefficient_v = 0.0;
+        if self.first_order {
+            let scale = -gm / (c2 * r.powi(2));
+            coefficient_n += scale * ((1.0 + 3.0 * eta) * v2 - 2.0 * (2.0 + eta) * gm / r - 1.5 * eta * r_dot.powi(2));
+            coefficient_v += scale * -2.0 * (2.0 - eta) * r_dot;
+        }
+        if self.radiation {
+            let scale = 1.6 * eta * gm.powi(2) / (c2.powi(2) * self.speed_of_light * r.powi(3));
+            coefficient_n += scale * (3.0 * v2 + 17.0 / 3.0 * gm / r) * r_dot;
+            coefficient_v += scale * -(v2 + 3.0 * gm / r);
+        }
+
+        let share = source.mass / total_mass;
+        (share * (coefficient_n * n.0 + coefficient_v * v.0), share * (coefficient_n * n.1 + coefficient_v * v.1))
+    }
+}
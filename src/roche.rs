@@ -0,0 +1,131 @@
+use body::{Body, BodyClass, BodyId};
+
+// What becomes of a body torn apart by tides
+#[derive(Copy, Clone, PartialEq)]
+pub enum Disruption {
+    Off,
+    // A string of fragments along the line to the primary, where the tides stretch it
+    Fragments,
+    // A ring of particles around the primary at the distance the body was torn apart,
+    // skipping the orbits it takes the debris to shear out into one. The ring circles the
+    // primary with its momentum rather than the body's, so momentum isn't conserved.
+    Ring,
+}
+
+impl Disruption {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Disruption::Off => "OFF",
+            Disruption::Fragments => "FRAGMENTS",
+            Disruption::Ring => "RING",
+        }
+    }
+
+    pub fn next(&self) -> Disruption {
+        match *self {
+            Disruption::Off => Disruption::Fragments,
+            Disruption::Fragments => Disruption::Ring,
+            Disruption::Ring => Disruption::Off,
+        }
+    }
+}
+
+// Tidal disruption of free massive bodies that come within the Roche limit of a heavier
+// body, where its tides overcome their self-gravity. They break up into `count` pieces of
+// equal mass and the same density, which are held together by their own strength and
+// don't break up again.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Roche {
+    pub disruption: Disruption,
+    pub count: usize,
+}
+
+impl Roche {
+    pub fn new(disruption: Disruption, count: usize) -> Roche {
+        Roche {
+            disruption,
+            count,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.disruption != Disruption::Off && self.count > 1
+    }
+
+    // Bodies inside the Roche limit of another, along with the one whose limit they are
+    // deepest inside. Debris and point masses, which have no room to break up, are left out.
+    pub fn disruptions(&self, bodies: &[Body], debris: &[BodyId]) -> Vec<(usize, usize)> {
+        if !self.is_enabled() {
+            return vec![];
+        }
+        (0..bodies.len()).filter_map(|i| {
+            let body = &bodies[i];
+            if body.class != BodyClass::Massive || !body.motion.is_free() || body.size <= 0.0 || !(body.density > 0.0 && body.density.is_finite()) || debris.contains(&body.id) {
+                return None;
+            }
+            (0..bodies.len())
+                .filter(|&j| j != i && bodies[j].class == BodyClass::Massive && bodies[j].mass > body.mass)
+                .map(|j| {
+                    let distance = ((body.x - bodies[j].x).powi(2) + (body.y - bodies[j].y).powi(2)).sqrt();
+                    (j, distance / roche_limit(&bodies[j], body))
+                })
+                .filter(|&(_, depth)| depth < 1.0)
+                .fold(None, |deepest: Option<(usize, f64)>, (j, depth)| match deepest {
+                    Some((_, deepest_depth)) if deepest_depth <= depth => deepest,
+                    _ => Some((j, depth)),
+                })
+                .map(|(j, _)| (i, j))
+        }).collect()
+    }
+
+    // The pieces `body` breaks up into inside the Roche limit of `primary`, with the mass
+    // and volume of the body, and the angular momentum of its spin shared out by moment of
+    // inertia. Fragments also keep its centre of mass and momentum, a ring doesn't.
+    pub fn break_up(&self, body: &Body, primary: &Body, gravity_const: f64) -> Vec<Body> {
+        let mass = body.mass / self.count as f64;
+        let size = (mass / body.density / (4.0 / 3.0 * ::PI)).cbrt();
+        let offset = (body.x - primary.x, body.y - primary.y);
+        let distance = (offset.0.powi(2) + offset.1.powi(2)).sqrt();
+        if distance <= 0.0 {
+            return vec![];
+        }
+        let axis = (offset.0 / distance, offset.1 / distance);
+        let velocity = (body.v_x - primary.v_x, body.v_y - primary.v_y);
+        let angular_momentum = offset.0 * velocity.1 - offset.1 * velocity.0;
+        let spin = body.moment_of_inertia() * body.spin / (self.count as f64 * 0.4 * mass * size.powi(2));
+
+        (0..self.count).map(|k| {
+            let (position, velocity) = match self.disruption {
+                Disruption::Ring => {
+                    // On a circular orbit in the sense the body went around
+                    let angle = offset.1.atan2(offset.0) + 2.0 * ::PI * k as f64 / self.count as f64;
+                    let direction = (angle.cos(), angle.sin());
+                    let side = if angular_momentum < 0.0 { -1.0 } else { 1.0 };
+                    let speed = (gravity_const * primary.mass / distance).sqrt();
+                    (
+                        (primary.x + distance * direction.0, primary.y + distance * direction.1),
+                        (primary.v_x - side * speed * direction.1, primary.v_y + side * speed * direction.0),
+                    )
+                },
+                _ => {
+                    // Evenly spaced just apart and moving with the body, for the tides to
+                    // pull apart along its orbit
+                    let along = 2.5 * size * (k as f64 - 0.5 * (self.count - 1) as f64);
+                    ((body.x + along * axis.0, body.y + along * axis.1), (body.v_x, body.v_y))
+                },
+            };
+            let mut piece = Body::with_mass(position.0, position.1, velocity.0, velocity.1, mass, size);
+            piece.charge = body.charge / self.count as f64;
+            piece.spin = spin;
+            piece.temperature = body.temperature;
+            piece
+        }).collect()
+    }
+}
+
+// Distance from `primary` within which the tides of its mass pull a fluid body of the
+// density of `satellite` apart, 2.44 times the radius the primary would have at that
+// density. It only depends on the mass of the primary, so point masses have one too.
+pub fn roche_limit(primary: &Body, satellite: &Body) -> f64 {
+    2.44 * (primary.mass / satellite.density / (4.0 / 3.0 * ::PI)).cbrt()
+}
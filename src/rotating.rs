@@ -0,0 +1,68 @@
+use body::{Body, BodyClass};
+use motion::Motion;
+
+// A frame rotating about the origin at angular velocity `omega`, counterclockwise in
+// simulation coordinates. Free bodies feel the centrifugal and Coriolis pseudo-forces,
+// so that bodies at rest in it, like the pinned primaries of the circular restricted
+// three-body problem, stand for bodies on circular orbits in the inertial frame.
+#[derive(Copy, Clone, PartialEq)]
+pub struct RotatingFrame {
+    pub omega: f64,
+}
+
+impl RotatingFrame {
+    pub fn new(omega: f64) -> RotatingFrame {
+        RotatingFrame {
+            omega,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.omega != 0.0
+    }
+
+    // Frame of the circular restricted three-body problem, along with its two primaries
+    // pinned on the x axis with their barycentre at the origin, where the lighter one is
+    // at rest on a circular orbit around the heavier one at `separation`
+    pub fn circular_restricted(primary: Body, secondary: Body, separation: f64, gravity_const: f64) -> (RotatingFrame, Body, Body) {
+        let total_mass = primary.mass + secondary.mass;
+        let mu = secondary.mass / total_mass;
+        let place = |body: Body, x: f64| {
+            let mut body = body;
+            body.set_state(((x, 0.0), (0.0, 0.0)));
+            body.motion = Motion::Pinned;
+            body
+        };
+        let omega = (gravity_const * total_mass / separation.powi(3)).sqrt();
+        (RotatingFrame::new(omega), place(primary, -mu * separation), place(secondary, (1.0 - mu) * separation))
+    }
+
+    // Centrifugal and Coriolis acceleration of a body moving through the frame
+    pub fn acceleration(&self, body: &Body) -> (f64, f64) {
+        let omega_squared = self.omega.powi(2);
+        (
+            omega_squared * body.x + 2.0 * self.omega * body.v_y,
+            omega_squared * body.y - 2.0 * self.omega * body.v_x,
+        )
+    }
+
+    // Effective potential of the frame and the Newtonian attraction of the massive bodies
+    // other than `exclude`, with the sign convention of the restricted problem where it is
+    // positive and the centrifugal term adds to it
+    pub fn effective_potential(&self, x: f64, y: f64, bodies: &[Body], exclude: Option<usize>, gravity_const: f64) -> f64 {
+        let gravity: f64 = bodies.iter()
+            .enumerate()
+            .filter(|&(j, source)| Some(j) != exclude && source.class == BodyClass::Massive)
+            .map(|(_, source)| gravity_const * source.mass / ((x - source.x).powi(2) + (y - source.y).powi(2)).sqrt())
+            .sum();
+        0.5 * self.omega.powi(2) * (x.powi(2) + y.powi(2)) + gravity
+    }
+
+    // Jacobi constant C = 2 U - v^2 of the body at `index`, the one integral of motion of
+    // the restricted problem. The body can only reach places where 2 U >= C, bounded by
+    // the zero-velocity curves 2 U = C.
+    pub fn jacobi_constant(&self, bodies: &[Body], index: usize, gravity_const: f64) -> f64 {
+        let body = &bodies[index];
+        2.0 * self.effective_potential(body.x, body.y, bodies, Some(index), gravity_const) - (body.v_x.powi(2) + body.v_y.powi(2))
+    }
+}
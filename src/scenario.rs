@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use body::{Body, BodyClass};
+use system::System;
+use softening::Softening;
+use units::UnitSystem;
+use motion::{Motion, Path};
+use orbit::{Elements, Orbit};
+use potential::{ExternalField, Potential};
+use force::{Coulomb, Mond, Newtonian, PowerLaw, Yukawa};
+use integrator::{AdaptiveStep, Integrator};
+use regularization::Regularization;
+use escape::Escape;
+use fragmentation::Fragmentation;
+use roche::{Disruption, Roche};
+use spin::TidalTorque;
+use thermal::Thermal;
+use rotating::RotatingFrame;
+
+// A scenario is a plain text file with one directive per line, e.g.
+//
+//   units astronomical
+//   softening plummer 0.001
+//   force yukawa length=2
+//   relativity pn1 gw c=100
+//   integrator dormand-prince tolerance=1e-9
+//   regularization 0.01
+//   escape 50 remove
+//   binaries
+//   fragmentation 0.5 count=8 min_mass=1e-6
+//   roche ring count=200
+//   tides 0.001
+//   thermal capacity=1000 emissivity=0.9
+//   restricted mass1=1 mass2=0.0123 separation=1
+//   camera 0 0 300
+//   body x=1 y=0 vx=0 vy=6.28 size=0.0001 density=1
+//   body x=2 y=0 vx=0 vy=4.44 mass=0.001
+//   body around=0 a=1.5 e=0.2 periapsis=1.57 anomaly=0 mass=0.0001
+//   belt center=0 count=2000 inner=2.1 outer=3.3
+//   path 0,0,0 5,100,0 10,0,0 loop
+//   field nfw mass=100 scale=20 growth=5
+//
+// A body is given either a density, from which its mass follows, or an explicit mass
+// with an independent size (a point mass when the size is omitted). Bodies with `test=1`
+// and the particles of a `belt` are massless test particles, placed on circular orbits
+// around its `center` body, or the origin when it has none.
+//
+// `around=<n>` places a free body on an orbit around the n-th body instead of at x, y
+// with vx, vy, from its semi-major axis `a`, eccentricity `e`, argument of `periapsis`
+// and true `anomaly`, both in radians, revolving the other way with `retrograde=1`.
+//
+// Bodies can also be kinematic: `pinned=1` holds a body in place, `path=<n>` follows the
+// n-th `path` of time,x,y keyframes, and `a`, `e`, `periapsis`, `period` and `epoch`
+// put it on a fixed Kepler orbit around (`focus_x`, `focus_y`).
+//
+// `field` adds a background potential acting on every body: `uniform` (gx, gy), `point`
+// (mass), `log` (v0, core), `nfw` (mass, scale) or `disk` (Miyamoto-Nagai mass, a, b).
+// Its centre starts at x, y and moves with vx, vy, and `growth` ramps it in over time.
+//
+// `force` selects the interaction between bodies: `newtonian`, `power` (n), `yukawa`
+// (length), `mond` (a0) or `coulomb` (k), the latter using each body's `charge`.
+// `relativity` turns on the 1PN correction (`pn1`) and gravitational wave radiation
+// reaction (`gw`), optionally overriding the speed of light of the unit system.
+// `integrator` is either `leapfrog`, one step per frame, the adaptive `dormand-prince`
+// with an error `tolerance`, or `wisdom-holman` for systems dominated by one body, taking
+// steps of at most `step`. `regularization <radius>` treats free massive bodies that come
+// closer than the radius as regularized pairs, as long as there is no softening.
+// `escape <radius>` reports bodies leaving the system beyond the radius, and removes
+// them as well with `remove`. `binaries` reports massive bodies pairing up into bound
+// binaries and coming apart again. `fragmentation <threshold>` breaks up the product of
+// an impact between free bodies above the threshold specific impact energy into a
+// remnant and up to `count` fragments no lighter than `min_mass`, which only merge in
+// impacts until they have flown clear. `roche fragments` or `roche ring` tears free
+// bodies apart into `count` pieces once they come within the Roche limit of a heavier
+// body, as a string of fragments or a ring around it. `tides <k2/Q>` lets bodies brake
+// each other's spins towards their orbits, which are set with `spin` on a body.
+// `thermal` overrides the specific heat `capacity` and `emissivity` bodies heat up in
+// impacts and radiate with, of rock by default, and turns heating off with `capacity=0`.
+// Bodies start at the `temperature` they are given, in kelvin.
+//
+// `restricted` sets up the circular restricted three-body problem: the simulation runs in
+// a frame rotating with two primaries of `mass1` and `mass2` (and `size1`, `size2`), which
+// are pinned on the x axis `separation` apart around their barycentre at the origin. Test
+// particles added after it move under the centrifugal and Coriolis forces of the frame.
+//
+// Blank lines and lines starting with '#' are ignored.
+pub struct Scenario {
+    pub system: System,
+    pub camera: Option<(f64, f64, f64)>,
+}
+
+impl Scenario {
+    pub fn load(path: &str) -> Result<Scenario, String> {
+        let mut contents = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| format!("Failed to read scenario {}: {}", path, e))?;
+        Scenario::parse(&contents).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(contents: &str) -> Result<Scenario, String> {
+        let mut scenario = Scenario {
+            system: System::new(),
+            camera: None,
+        };
+
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            scenario.parse_directive(&tokens).map_err(|e| format!("line {}: {}", line_num + 1, e))?;
+        }
+
+        Ok(scenario)
+    }
+
+    fn parse_directive(&mut self, tokens: &[&str]) -> Result<(), String> {
+        let system = &mut self.system;
+        match tokens[0] {
+            "units" => {
+                let name = tokens.get(1).ok_or("Missing unit system")?;
+                let units = UnitSystem::from_name(name).ok_or(format!("Unknown unit system '{}'", name))?;
+                system.set_units(units);
+            },
+            "g" => {
+                system.gravity_const = parse_number(tokens, 1)?;
+            },
+            "timescale" => {
+                system.time_scale = parse_number(tokens, 1)?;
+            },
+            "softening" => {
+                system.softening = match tokens.get(1) {
+                    Some(&"none") => Softening::None,
+                    Some(&"plummer") => Softening::Plummer(parse_number(tokens, 2)?),
+                    Some(&"spline") => Softening::Spline(parse_number(tokens, 2)?),
+                    _ => return Err(String::from("Expected softening none, plummer <length> or spline <length>")),
+                };
+            },
+            "force" => {
+                let kind = tokens.get(1).ok_or("Missing force law")?;
+                let attributes = parse_attributes(&tokens[2..], &["n", "length", "a0", "k"])?;
+                let get = |key: &str, default: f64| *attributes.get(key).unwrap_or(&default);
+                let positive = |key: &str| match get(key, 1.0) {
+                    value if value > 0.0 => Ok(value),
+                    _ => Err(format!("The {} of the {} force must be positive", key, kind)),
+                };
+                system.force_law = match *kind {
+                    "newtonian" => Box::new(Newtonian),
+                    "power" => Box::new(PowerLaw { exponent: get("n", 2.0) }),
+                    "yukawa" => Box::new(Yukawa { length: positive("length")? }),
+                    "mond" => Box::new(Mond { a0: positive("a0")? }),
+                    "coulomb" => Box::new(Coulomb { k: get("k", 1.0) }),
+                    _ => return Err(format!("Unknown force law '{}'", kind)),
+                };
+            },
+            "relativity" => {
+                for token in &tokens[1..] {
+                    match *token {
+                        "pn1" => system.relativity.first_order = true,
+                        "gw" => system.relativity.radiation = true,
+                        _ => {
+                            let attributes = parse_attributes(&[token], &["c"])?;
+                            system.relativity.speed_of_light = attributes["c"];
+                        },
+                    }
+                }
+            },
+            "integrator" => {
+                let kind = tokens.get(1).ok_or("Missing integrator")?;
+                let attributes = parse_attributes(&tokens[2..], &["tolerance", "step"])?;
+                let integrator = match *kind {
+                    "leapfrog" => Integrator::Leapfrog,
+                    "dormand-prince" => Integrator::DormandPrince(AdaptiveStep::new(*attributes.get("tolerance").unwrap_or(&1e-8))),
+                    "wisdom-holman" => Integrator::WisdomHolman { max_step: *attributes.get("step").unwrap_or(&0.0) },
+                    _ => return Err(format!("Unknown integrator '{}'", kind)),
+                };
+                system.set_integrator(integrator, 0.0);
+            },
+            "regularization" => {
+                system.regularization = Regularization::new(parse_number(tokens, 1)?);
+            },
+            "binaries" => {
+                system.detect_binaries = true;
+            },
+            "escape" => {
+                let remove = match tokens.get(2) {
+                    None => false,
+                    Some(&"remove") => true,
+                    Some(token) => return Err(format!("Unknown escape option '{}'", token)),
+                };
+                system.escape = Escape::new(parse_number(tokens, 1)?, remove);
+            },
+            "restricted" => {
+                let attributes = parse_attributes(&tokens[1..], &["mass1", "mass2", "separation", "size1", "size2"])?;
+                let get = |key: &str, default: f64| *attributes.get(key).unwrap_or(&default);
+                let primary = Body::with_mass(0.0, 0.0, 0.0, 0.0, get("mass1", 1.0), get("size1", 0.0));
+                let secondary = Body::with_mass(0.0, 0.0, 0.0, 0.0, get("mass2", 0.01), get("size2", 0.0));
+                let (frame, primary, secondary) = RotatingFrame::circular_restricted(primary, secondary, get("separation", 1.0), system.gravity_const);
+                system.frame = frame;
+                system.add_body(primary);
+                system.add_body(secondary);
+            },
+            "fragmentation" => {
+                let threshold = parse_number(tokens, 1)?;
+                if threshold <= 0.0 {
+                    return Err(String::from("The fragmentation threshold must be positive"));
+                }
+                let attributes = parse_attributes(&tokens[2..], &["count", "min_mass"])?;
+                system.fragmentation = Fragmentation::new(
+                    threshold,
+                    parse_count(&attributes)?,
+                    *attributes.get("min_mass").unwrap_or(&0.0));
+            },
+            "roche" => {
+                let disruption = match tokens.get(1) {
+                    Some(&"fragments") => Disruption::Fragments,
+                    Some(&"ring") => Disruption::Ring,
+                    _ => return Err(String::from("Expected roche fragments or roche ring")),
+                };
+                let attributes = parse_attributes(&tokens[2..], &["count"])?;
+                system.roche = Roche::new(disruption, parse_count(&attributes)?);
+            },
+            "tides" => {
+                system.tides = TidalTorque::new(parse_number(tokens, 1)?);
+            },
+            "thermal" => {
+                let attributes = parse_attributes(&tokens[1..], &["capacity", "emissivity"])?;
+                system.thermal = Thermal::new(
+                    *attributes.get("capacity").unwrap_or(&system.thermal.heat_capacity),
+                    *attributes.get("emissivity").unwrap_or(&system.thermal.emissivity),
+                    system.thermal.stefan_boltzmann);
+            },
+            "camera" => {
+                self.camera = Some((parse_number(tokens, 1)?, parse_number(tokens, 2)?, parse_number(tokens, 3)?));
+            },
+            "body" => {
+                let attributes = parse_attributes(&tokens[1..], &["x", "y", "vx", "vy", "density", "size", "mass", "test", "charge",
+                    "pinned", "path", "a", "e", "periapsis", "period", "epoch", "focus_x", "focus_y", "around", "anomaly", "retrograde", "spin", "temperature"])?;
+                let get = |key: &str, default: f64| *attributes.get(key).unwrap_or(&default);
+                let body = if get("test", 0.0) != 0.0 {
+                    Body::test_particle(get("x", 0.0), get("y", 0.0), get("vx", 0.0), get("vy", 0.0), get("size", 0.0))
+                } else if attributes.contains_key("mass") {
+                    if attributes.contains_key("density") {
+                        return Err(String::from("A body can't specify both mass and density"));
+                    }
+                    Body::with_mass(get("x", 0.0), get("y", 0.0), get("vx", 0.0), get("vy", 0.0), get("mass", 0.0), get("size", 0.0))
+                } else {
+                    Body::new(get("x", 0.0), get("y", 0.0), get("vx", 0.0), get("vy", 0.0), get("density", 1.0), get("size", 1.0))
+                };
+                let mut body = body;
+                body.charge = get("charge", 0.0);
+                body.spin = get("spin", 0.0);
+                body.temperature = get("temperature", 0.0);
+                if get("pinned", 0.0) != 0.0 {
+                    body.motion = Motion::Pinned;
+                } else if attributes.contains_key("path") {
+                    let index = get("path", 0.0) as usize;
+                    if index >= system.paths.len() {
+                        return Err(format!("Path {} is not defined above", index));
+                    }
+                    body.motion = Motion::Scripted(index);
+                } else if let Some(&index) = attributes.get("around") {
+                    let primary = *system.bodies.get(index as usize).ok_or("Primary is not a body defined above")?;
+                    if attributes.contains_key("period") {
+                        return Err(String::from("A body placed around another body can't also follow a fixed orbit"));
+                    }
+                    let e = get("e", 0.0);
+                    if e < 0.0 {
+                        return Err(String::from("The eccentricity can't be negative"));
+                    }
+                    if (e - 1.0).abs() < 1e-9 {
+                        return Err(String::from("Parabolic orbits can't be placed by their semi-major axis"));
+                    }
+                    let a = *attributes.get("a").ok_or("A body placed around another body needs a semi-major axis")?;
+                    if a <= 0.0 {
+                        return Err(String::from("The semi-major axis must be positive"));
+                    }
+                    // Hyperbolic orbits only reach as far round as their asymptotes
+                    let anomaly = get("anomaly", 0.0);
+                    if e > 1.0 && anomaly.sin().atan2(anomaly.cos()).abs() >= (-1.0 / e).acos() {
+                        return Err(String::from("The true anomaly lies beyond the asymptotes of the hyperbolic orbit"));
+                    }
+                    let elements = Elements {
+                        semi_major: if e > 1.0 { -a } else { a },
+                        eccentricity: e,
+                        retrograde: get("retrograde", 0.0) != 0.0,
+                        periapsis_arg: get("periapsis", 0.0),
+                        true_anomaly: anomaly,
+                        period: 0.0,
+                    };
+                    let mass = if body.class == BodyClass::Massive { body.mass } else { 0.0 };
+                    let (position, velocity) = elements.to_state(system.gravity_const * (primary.mass + mass));
+                    body.x = primary.x + position.0;
+                    body.y = primary.y + position.1;
+                    body.v_x = primary.v_x + velocity.0;
+                    body.v_y = primary.v_y + velocity.1;
+                } else if attributes.contains_key("a") {
+                    if !attributes.contains_key("period") {
+                        return Err(String::from("A Kepler orbit needs a period"));
+                    }
+                    if get("a", 0.0) <= 0.0 || get("period", 0.0) <= 0.0 {
+                        return Err(String::from("A Kepler orbit needs a positive semi-major axis and period"));
+                    }
+                    if !(get("e", 0.0) >= 0.0 && get("e", 0.0) < 1.0) {
+                        return Err(String::from("A Kepler orbit needs an eccentricity from 0 up to 1"));
+                    }
+                    body.motion = Motion::Kepler(Orbit {
+                        focus_x: get("focus_x", 0.0),
+                        focus_y: get("focus_y", 0.0),
+                        semi_major: get("a", 0.0),
+                        eccentricity: get("e", 0.0),
+                        periapsis_arg: get("periapsis", 0.0),
+                        period: get("period", 0.0),
+                        epoch: get("epoch", 0.0),
+                    });
+                }
+                if let Some(state) = system.prescribed_state(&body.motion, 0.0) {
+                    body.set_state(state);
+                }
+                system.add_body(body);
+            },
+            "path" => {
+                let mut path = Path { keyframes: vec!(), looped: false };
+                for token in &tokens[1..] {
+                    if *token == "loop" {
+                        path.looped = true;
+                        continue;
+                    }
+                    let values = token.split(',').map(|v| v.parse::<f64>()).collect::<Result<Vec<f64>, _>>()
+                        .map_err(|_| format!("Invalid keyframe '{}'", token))?;
+                    if values.len() != 3 {
+                        return Err(format!("Expected keyframe time,x,y, found '{}'", token));
+                    }
+                    path.keyframes.push((values[0], values[1], values[2]));
+                }
+                if path.keyframes.is_empty() {
+                    return Err(String::from("A path needs at least one keyframe"));
+                }
+                system.paths.push(path);
+            },
+            "field" => {
+                let kind = tokens.get(1).ok_or("Missing field type")?;
+                let attributes = parse_attributes(&tokens[2..], &["x", "y", "vx", "vy", "growth", "gx", "gy", "mass", "v0", "core", "scale", "a", "b"])?;
+                let get = |key: &str, default: f64| *attributes.get(key).unwrap_or(&default);
+                let potential = match *kind {
+                    "uniform" => Potential::Uniform { g_x: get("gx", 0.0), g_y: get("gy", 0.0) },
+                    "point" => Potential::PointMass { mass: get("mass", 1.0) },
+                    "log" => Potential::Logarithmic { v0: get("v0", 1.0), core: get("core", 1.0) },
+                    "nfw" => Potential::Nfw { mass: get("mass", 1.0), scale: get("scale", 1.0) },
+                    "disk" => Potential::MiyamotoNagai { mass: get("mass", 1.0), a: get("a", 1.0), b: get("b", 0.1) },
+                    _ => return Err(format!("Unknown field type '{}'", kind)),
+                };
+                let mut field = ExternalField::new(potential);
+                field.x = get("x", 0.0);
+                field.y = get("y", 0.0);
+                field.v_x = get("vx", 0.0);
+                field.v_y = get("vy", 0.0);
+                field.growth_time = get("growth", 0.0);
+                system.fields.push(field);
+            },
+            "belt" => {
+                let attributes = parse_attributes(&tokens[1..], &["center", "count", "inner", "outer", "size"])?;
+                let get = |key: &str, default: f64| *attributes.get(key).unwrap_or(&default);
+                let center = match attributes.get("center") {
+                    Some(&index) => *system.bodies.get(index as usize).ok_or("Belt center is not a body defined above")?,
+                    None => Body::with_mass(0.0, 0.0, 0.0, 0.0, 0.0, 0.0),
+                };
+                let (inner, outer) = (get("inner", 1.0), get("outer", 2.0));
+
+                // Spread the particles evenly over the annulus on circular orbits around the
+                // center body and any background fields
+                for k in 0..get("count", 1000.0) as usize {
+                    let angle = k as f64 * ::PI * (3.0 - 5f64.sqrt());
+                    let radius = (inner.powi(2) + (outer.powi(2) - inner.powi(2)) * (k as f64 * 0.754_877_666_246_692_7).fract()).sqrt();
+                    let (x, y) = (center.x + radius * angle.cos(), center.y + radius * angle.sin());
+                    let mut inward = system.gravity_const * center.mass / radius.powi(2);
+                    for field in &system.fields {
+                        let a = field.acceleration(x, y, 0.0, system.gravity_const);
+                        inward -= a.0 * angle.cos() + a.1 * angle.sin();
+                    }
+                    let speed = (radius * inward.max(0.0)).sqrt();
+                    system.add_body(Body::test_particle(
+                        x,
+                        y,
+                        center.v_x - speed * angle.sin(),
+                        center.v_y + speed * angle.cos(),
+                        get("size", 0.0)));
+                }
+            },
+            directive => return Err(format!("Unknown directive '{}'", directive)),
+        }
+        Ok(())
+    }
+}
+
+fn parse_number(tokens: &[&str], index: usize) -> Result<f64, String> {
+    let token = tokens.get(index).ok_or(format!("Missing argument {} for '{}'", index, tokens[0]))?;
+    match token.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        _ => Err(format!("Invalid number '{}'", token)),
+    }
+}
+
+// The number of pieces a body breaks up into, at least two whole ones
+fn parse_count(attributes: &HashMap<&str, f64>) -> Result<usize, String> {
+    let count = *attributes.get("count").unwrap_or(&8.0);
+    if count < 2.0 || count.fract() != 0.0 {
+        return Err(format!("Expected a whole count of at least 2, found {}", count));
+    }
+    Ok(count as usize)
+}
+
+fn parse_attributes<'a>(tokens: &[&'a str], allowed: &[&str]) -> Result<HashMap<&'a str, f64>, String> {
+    let mut attributes = HashMap::new();
+    for token in tokens {
+        let mut split = token.splitn(2, '=');
+        let key = split.next().unwrap();
+        if !allowed.contains(&key) {
+            return Err(format!("Unknown attribute '{}'", key));
+        }
+        let value = split.next().ok_or(format!("Expected key=value, found '{}'", token))?;
+        match value.parse::<f64>() {
+            Ok(number) if number.is_finite() => attributes.insert(key, number),
+            _ => return Err(format!("Invalid number '{}' for '{}'", value, key)),
+        };
+    }
+    Ok(attributes)
+}
@@ -0,0 +1,68 @@
+// Gravitational softening kernels. Each kernel returns the factor `k` such that the
+// acceleration towards a source of mass `m` at offset `d` is `G * m * k * d`.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Softening {
+    None,
+    Plummer(f64),
+    Spline(f64),
+}
+
+impl Softening {
+    pub fn inverse_cube(&self, distance: f64) -> f64 {
+        match *self {
+            Softening::None => {
+                let min_distance = 0.0001;
+                let distance = if distance < min_distance { min_distance } else { distance };
+                1.0 / distance.powi(3)
+            },
+            Softening::Plummer(epsilon) => {
+                1.0 / (distance.powi(2) + epsilon.powi(2)).powf(1.5)
+            },
+            Softening::Spline(length) => {
+                // Cubic spline kernel (Monaghan & Lattanzio), exactly Newtonian beyond `length`
+                if distance >= length || length <= 0.0 {
+                    return 1.0 / distance.powi(3);
+                }
+                let u = distance / length;
+                let h_inv3 = 1.0 / length.powi(3);
+                if u < 0.5 {
+                    h_inv3 * (32.0 / 3.0 + u.powi(2) * (32.0 * u - 38.4))
+                } else {
+                    h_inv3 * (64.0 / 3.0 - 48.0 * u + 38.4 * u.powi(2) - (32.0 / 3.0) * u.powi(3) - (1.0 / 15.0) / u.powi(3))
+                }
+            },
+        }
+    }
+
+    pub fn length(&self) -> f64 {
+        match *self {
+            Softening::None => 0.0,
+            Softening::Plummer(length) | Softening::Spline(length) => length,
+        }
+    }
+
+    pub fn with_length(&self, length: f64) -> Softening {
+        match *self {
+            Softening::None => Softening::None,
+            Softening::Plummer(_) => Softening::Plummer(length),
+            Softening::Spline(_) => Softening::Spline(length),
+        }
+    }
+
+    // Cycles None -> Plummer -> Spline, keeping the current softening length
+    pub fn next(&self, length: f64) -> Softening {
+        match *self {
+            Softening::None => Softening::Plummer(length),
+            Softening::Plummer(_) => Softening::Spline(length),
+            Softening::Spline(_) => Softening::None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Softening::None => "NONE",
+            Softening::Plummer(_) => "PLUMMER",
+            Softening::Spline(_) => "SPLINE",
+        }
+    }
+}
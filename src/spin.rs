@@ -0,0 +1,52 @@
+use body::{Body, BodyClass};
+
+// Tidal torques on the spins of massive bodies. Each body raises tides on the others,
+// whose lag behind the line between them brakes or speeds up their spin towards turning
+// at the angular velocity of the pair, as the Moon keeps one face to the Earth. How
+// strongly is set by `dissipation`, the ratio k2 / Q of the Love number to the tidal
+// quality factor, around 1e-3 for rocky bodies. The torques back on the orbits are small
+// compared to the orbital angular momentum and are left out.
+#[derive(Copy, Clone, PartialEq)]
+pub struct TidalTorque {
+    pub dissipation: f64,
+}
+
+impl TidalTorque {
+    pub fn new(dissipation: f64) -> TidalTorque {
+        TidalTorque {
+            dissipation,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.dissipation > 0.0
+    }
+
+    // Change in the spin of every body over `dt`, which never carries a spin past the
+    // angular velocity of the pair braking it
+    pub fn spin_changes(&self, bodies: &[Body], gravity_const: f64, dt: f64) -> Vec<f64> {
+        bodies.iter().enumerate().map(|(i, body)| {
+            if !self.is_enabled() || body.class != BodyClass::Massive || body.mass <= 0.0 || body.size <= 0.0 {
+                return 0.0;
+            }
+            let mut change = 0.0;
+            for (j, other) in bodies.iter().enumerate() {
+                if j == i || other.class != BodyClass::Massive || other.mass <= 0.0 {
+                    continue;
+                }
+                let offset = (other.x - body.x, other.y - body.y);
+                let velocity = (other.v_x - body.v_x, other.v_y - body.v_y);
+                let distance_squared = offset.0.powi(2) + offset.1.powi(2);
+                if distance_squared <= 0.0 {
+                    continue;
+                }
+                let orbital = (offset.0 * velocity.1 - offset.1 * velocity.0) / distance_squared;
+                // Torque 3/2 k2/Q G m^2 R^5 / d^6 over the moment of inertia 2/5 M R^2
+                let rate = 3.75 * self.dissipation * gravity_const * other.mass.powi(2) * body.size.powi(3) / (body.mass * distance_squared.powi(3));
+                let difference = orbital - (body.spin + change);
+                change += difference.signum() * (rate * dt).min(difference.abs());
+            }
+            change
+        }).collect()
+    }
+}
@@ -1,80 +1,766 @@
-use body::Body;
+use std::mem;
+use body::{Body, BodyClass, BodyId};
 use sdl2::render::WindowCanvas;
+use sdl2::pixels::Color;
+use sdl2::rect::Point;
+use fuax_gfx::FauxGFX;
 use cam::Cam;
+use softening::Softening;
+use units::UnitSystem;
+use motion::{Motion, Path};
+use orbit::Elements;
+use hierarchy::{Hierarchy, Influence};
+use potential::ExternalField;
+use force::{ForceLaw, Newtonian};
+use relativity::Relativity;
+use integrator::{self, Integrator, State};
+use regularization::{self, Regularization};
+use escape::{self, Escape};
+use event::{Event, EventStream};
+use merger::{Merger, MergerTree};
+use fragmentation::{self, Fragmentation};
+use roche::{self, Roche};
+use spin::TidalTorque;
+use thermal::Thermal;
+use lagrange;
+use rotating::RotatingFrame;
 
 pub struct System {
     pub bodies: Vec<Body>,
+    pub softening: Softening,
+    pub units: UnitSystem,
+    pub gravity_const: f64,
+    pub time_scale: f64,
+    pub paths: Vec<Path>,
+    pub fields: Vec<ExternalField>,
+    pub force_law: Box<dyn ForceLaw>,
+    pub relativity: Relativity,
+    pub integrator: Integrator,
+    pub regularization: Regularization,
+    // Pairs whose relative motion is being regularized this frame
+    pub close_pairs: Vec<(usize, usize)>,
+    pub influence: Influence,
+    // Frame the simulation runs in, inertial unless set up for the restricted problem
+    pub frame: RotatingFrame,
+    pub escape: Escape,
+    // Whether binaries are looked for, which takes a pass over every pair each update
+    pub detect_binaries: bool,
+    // Bound binaries and escaped bodies as of the last update
+    pub binaries: Vec<(BodyId, BodyId)>,
+    pub escaped: Vec<BodyId>,
+    pub events: EventStream,
+    pub mergers: MergerTree,
+    pub fragmentation: Fragmentation,
+    pub roche: Roche,
+    // Fragments of impacts and pieces of tidally disrupted bodies, which the tides don't
+    // break up again
+    pub debris: Vec<BodyId>,
+    // Fragments of recent impacts along with the time until which they only merge
+    pub spared: Vec<(BodyId, f64)>,
+    pub tides: TidalTorque,
+    pub thermal: Thermal,
+    next_id: BodyId,
 }
 
 impl System {
     pub fn new() -> Self {
         System {
-            bodies: vec!()
+            bodies: vec!(),
+            softening: Softening::None,
+            units: UnitSystem::Pixel,
+            gravity_const: UnitSystem::Pixel.gravity_const(),
+            time_scale: UnitSystem::Pixel.time_scale(),
+            paths: vec!(),
+            fields: vec!(),
+            force_law: Box::new(Newtonian),
+            relativity: Relativity::new(UnitSystem::Pixel.speed_of_light()),
+            integrator: Integrator::Leapfrog,
+            regularization: Regularization::new(0.0),
+            close_pairs: vec!(),
+            influence: Influence::Laplace,
+            frame: RotatingFrame::new(0.0),
+            escape: Escape::new(0.0, false),
+            detect_binaries: false,
+            binaries: vec!(),
+            escaped: vec!(),
+            events: EventStream::new(UnitSystem::Pixel),
+            mergers: MergerTree::new(),
+            fragmentation: Fragmentation::new(0.0, 8, 0.0),
+            roche: Roche::new(roche::Disruption::Off, 8),
+            debris: vec!(),
+            spared: vec!(),
+            tides: TidalTorque::new(0.0),
+            thermal: Thermal::of_units(UnitSystem::Pixel),
+            next_id: 1,
         }
     }
 
-    pub fn add(&mut self, x: f32, y: f32, v_x: f32, v_y: f32, density: f32, size: f32) {
-        let body = Body::new(x, y, v_x, v_y, density, size);
-        self.bodies.push(body);
+    pub fn set_units(&mut self, units: UnitSystem) {
+        // A heat capacity other than that of rock was set on purpose and is kept
+        if self.thermal.heat_capacity == self.units.heat_capacity() {
+            self.thermal.heat_capacity = units.heat_capacity();
+        }
+        self.thermal.stefan_boltzmann = units.stefan_boltzmann();
+        self.units = units;
+        self.gravity_const = units.gravity_const();
+        self.time_scale = units.time_scale();
+        self.relativity.speed_of_light = units.speed_of_light();
+        self.events.units = units;
+    }
+
+    // Adds a body under a new ID, which is returned
+    pub fn add_body(&mut self, body: Body) -> BodyId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.bodies.push(Body { id, ..body });
+        self.events.emit(Event::BodyAdded(id));
+        id
+    }
+
+    // Removes every body along with what was recorded about them, keeping the settings
+    pub fn clear(&mut self) {
+        for body in self.bodies.drain(..) {
+            self.events.emit(Event::Removed(Box::new(body)));
+        }
+        self.close_pairs.clear();
+        self.binaries.clear();
+        self.escaped.clear();
+        self.debris.clear();
+        self.spared.clear();
+        self.mergers = MergerTree::new();
+    }
+
+    pub fn index_of(&self, id: BodyId) -> Option<usize> {
+        self.bodies.iter().position(|body| body.id == id)
+    }
+
+    pub fn body(&self, id: BodyId) -> Option<&Body> {
+        self.bodies.iter().find(|body| body.id == id)
     }
 
-    pub fn update(&mut self, mult: &f32, total_time: &f32) {
-        let mut to_remove: Vec<usize> = vec!();
+    // Switches to `integrator`, bringing the velocities of the bodies at `time` in line
+    // with what it expects
+    pub fn set_integrator(&mut self, integrator: Integrator, time: f64) {
+        let accelerations = if self.integrator == Integrator::Leapfrog {
+            self.accelerations(&self.bodies, time)
+        } else {
+            vec![]
+        };
+        self.integrator.hand_over(&integrator, &mut self.bodies, &accelerations);
+        self.integrator = integrator;
+    }
+
+    pub fn update(&mut self, mult: &f64, total_time: &f64) {
+        let start_time = *total_time - *mult;
+
+        // Softened forces never become singular, and subtracting the point mass force of
+        // a pair from them would make them so. The Wisdom-Holman map already splits off
+        // the Kepler motion around its primary, and Kepler orbits don't hold in a rotating
+        // frame.
+        let previous_pairs = mem::take(&mut self.close_pairs);
+        if self.softening.length() == 0.0 && !self.integrator.is_wisdom_holman() && !self.frame.is_enabled() {
+            self.close_pairs = self.regularization.close_pairs(&self.bodies, self.gravity_const, *mult);
+        }
+
+        match self.integrator {
+            Integrator::Leapfrog => {
+                self.finish_pair_kicks(&previous_pairs);
+                self.leapfrog(mult, total_time);
+            },
+            Integrator::DormandPrince(mut adaptive) => {
+                for body in self.bodies.iter_mut() {
+                    body.past_x = body.x;
+                    body.past_y = body.y;
+                }
 
+                // Close pairs are drifted for half a piece on either side of integrating
+                // everything else, with pieces short compared to their orbits
+                let pieces = self.close_pair_pieces(*mult);
+                let piece = *mult / pieces as f64;
+                let mut substeps = 0;
+                let mut forced = false;
+                for index in 0..pieces {
+                    self.drift_close_pairs(0.5 * piece);
+                    let mut state: State = self.bodies.iter().map(|body| [body.x, body.y, body.v_x, body.v_y]).collect();
+                    integrator::dormand_prince(&mut state, start_time + index as f64 * piece, piece, &mut adaptive, |state, time| self.derivatives(state, time));
+                    for (body, values) in self.bodies.iter_mut().zip(state) {
+                        if body.motion.is_free() {
+                            body.x = values[0];
+                            body.y = values[1];
+                            body.v_x = values[2];
+                            body.v_y = values[3];
+                        }
+                    }
+                    self.drift_close_pairs(0.5 * piece);
+                    substeps += adaptive.substeps;
+                    forced |= adaptive.forced;
+                }
+                adaptive.substeps = substeps;
+                adaptive.forced = forced;
+                self.integrator = Integrator::DormandPrince(adaptive);
+
+                let state: State = self.bodies.iter().map(|body| [body.x, body.y, body.v_x, body.v_y]).collect();
+                let rates = self.derivatives(&state, *total_time);
+                for (body, rates) in self.bodies.iter_mut().zip(rates) {
+                    if body.motion.is_free() {
+                        body.a_x = rates[2];
+                        body.a_y = rates[3];
+                    }
+                }
+            },
+            Integrator::WisdomHolman { max_step } => {
+                let mut bodies = self.bodies.clone();
+                for body in bodies.iter_mut() {
+                    body.past_x = body.x;
+                    body.past_y = body.y;
+                }
+                if !self.frame.is_enabled() && integrator::wisdom_holman(self, &mut bodies, start_time, *mult, max_step) {
+                    self.bodies = bodies;
+                } else {
+                    // Falling back to a leapfrog step that ends synchronised, as the map
+                    // expects its velocities
+                    let integrator = self.integrator;
+                    self.set_integrator(Integrator::Leapfrog, start_time);
+                    self.leapfrog(mult, total_time);
+                    self.set_integrator(integrator, *total_time);
+                }
+            },
+        }
+
+        // Bodies that aren't free follow their prescribed motion whatever the integrator
         for i in 0..self.bodies.len() {
-            if to_remove.contains(&(i as usize)) {
-                continue;
+            let motion = self.bodies[i].motion;
+            match motion {
+                Motion::Free => {},
+                Motion::Pinned => {
+                    self.bodies[i].v_x = 0.0;
+                    self.bodies[i].v_y = 0.0;
+                },
+                _ => {
+                    let state = self.prescribed_state(&motion, *total_time).unwrap();
+                    self.bodies[i].set_state(state);
+                },
+            }
+        }
+
+        let spin_changes = self.tides.spin_changes(&self.bodies, self.gravity_const, *mult);
+        for (body, change) in self.bodies.iter_mut().zip(spin_changes) {
+            body.spin += change;
+            body.rotation = (body.rotation + body.spin * mult) % (2.0 * ::PI);
+            self.thermal.cool(body, *mult);
+        }
+
+        self.resolve_collisions(*total_time);
+        self.disrupt_satellites();
+        self.detect_encounters();
+    }
+
+    fn leapfrog(&mut self, mult: &f64, total_time: &f64) {
+        let accelerations = self.accelerations(&self.bodies, *total_time - *mult);
+        for (body, acceleration) in self.bodies.iter_mut().zip(accelerations) {
+            body.a_x = acceleration.0;
+            body.a_y = acceleration.1;
+        }
+        for body in self.bodies.iter_mut() {
+            if body.motion.is_free() {
+                body.update_self(mult, total_time);
+            }
+        }
+
+        // Close pairs were drifted in straight lines, follow their two-body orbit instead
+        for &(i, j) in &self.close_pairs {
+            let ((x, y), (v_x, v_y)) = regularization::relative_state(&self.bodies[i], &self.bodies[j]);
+            let mu = self.gravity_const * (self.bodies[i].mass + self.bodies[j].mass);
+            let state = regularization::levi_civita_drift((x - v_x * *mult, y - v_y * *mult), (v_x, v_y), mu, *mult);
+            let (first, second) = pair_mut(&mut self.bodies, i, j);
+            regularization::set_relative_state(first, second, state);
+        }
+    }
+
+    // The leapfrog finishes the previous step's kick with the current accelerations, which
+    // leave out the attraction within the current close pairs. Pairs that formed since then
+    // still need that half kick and pairs that broke up must not get it twice.
+    fn finish_pair_kicks(&mut self, previous_pairs: &[(usize, usize)]) {
+        let formed = self.close_pairs.iter().filter(|pair| !previous_pairs.contains(pair)).map(|&pair| (pair, 1.0));
+        let broken = previous_pairs.iter().filter(|pair| !self.close_pairs.contains(pair)).map(|&pair| (pair, -1.0));
+        let changes: Vec<((usize, usize), f64)> = formed.chain(broken).collect();
+
+        for ((i, j), sign) in changes {
+            for &(target, source) in &[(i, j), (j, i)] {
+                let source = self.bodies[source];
+                let target = &mut self.bodies[target];
+                let a = regularization::point_mass_acceleration(target, &source, self.gravity_const);
+                target.v_x += sign * 0.5 * target.past_mult * a.0;
+                target.v_y += sign * 0.5 * target.past_mult * a.1;
             }
+        }
+    }
+
+    // Number of pieces to split `interval` into so that the rest of the system sees every
+    // close pair turn only a little during each of them
+    fn close_pair_pieces(&self, interval: f64) -> usize {
+        let mut pieces = 1.0;
+        for &(i, j) in &self.close_pairs {
+            let (position, velocity) = regularization::relative_state(&self.bodies[i], &self.bodies[j]);
+            let distance = (position.0.powi(2) + position.1.powi(2)).sqrt();
+            let speed = (velocity.0.powi(2) + velocity.1.powi(2)).sqrt();
+            pieces = f64::max(pieces, (interval * speed / (0.05 * distance)).ceil());
+        }
+        pieces.min(1000.0) as usize
+    }
+
+    fn drift_close_pairs(&mut self, dt: f64) {
+        for &(i, j) in &self.close_pairs {
+            let (first, second) = pair_mut(&mut self.bodies, i, j);
+            regularization::drift_pair(first, second, self.gravity_const, dt);
+        }
+    }
 
+    // Acceleration of every body in `bodies` at the given time. Test particles feel the
+    // massive bodies but exert nothing, so only massive bodies act as sources.
+    pub fn accelerations(&self, bodies: &[Body], time: f64) -> Vec<(f64, f64)> {
+        let massive: Vec<usize> = (0..bodies.len()).filter(|&i| bodies[i].class == BodyClass::Massive).collect();
 
-            // Compute Gravity
-            self.bodies[i].a_x = 0.0;
-            self.bodies[i].a_y = 0.0;
+        bodies.iter().enumerate().map(|(i, body)| {
+            let mut acceleration = (0.0, 0.0);
+            // The point mass attraction within a close pair is handled by the regularization,
+            // so only what the force law adds to it is left
+            let partner = self.close_pairs.iter()
+                .filter_map(|&(first, second)| if first == i { Some(second) } else if second == i { Some(first) } else { None })
+                .next();
 
-            for j in 0..self.bodies.len() {
-                if to_remove.contains(&(j as usize)) {
+            for &j in &massive {
+                if i == j {
                     continue;
                 }
-                if i != j {
-                    let body_j = self.bodies[j];
-                    self.bodies[i].compute_gravity(body_j, mult);
-                    let body_i = self.bodies[i];
-
-                    // Collisions
-                    let rad_sum = (body_i.size + body_j.size).powi(2);
-                    let distance = (body_i.x - body_j.x).powi(2) + (body_i.y - body_j.y).powi(2);
-                    let is_collided = distance <= rad_sum;
-
-                    if is_collided {
-                        let (bigger_index, smaller_index) = if body_i.size >= body_j.size {
-                            (i, j)
-                        } else {
-                            (j, i)
-                        };
-
-                        to_remove.push(smaller_index);
-                        self.bodies[bigger_index].mass += self.bodies[smaller_index].mass;
-                        self.bodies[bigger_index].size = (self.bodies[bigger_index].mass * (3.0 / (4.0 * ::PI * self.bodies[bigger_index].density))).powf(1.0 / 3.0);
-                    }
+                let source = &bodies[j];
+                let a = if partner == Some(j) {
+                    // Unlike `Softening::None` a zero length Plummer kernel isn't clamped
+                    let a = self.force_law.acceleration(body, source, self.gravity_const, &Softening::Plummer(0.0));
+                    let point_mass = regularization::point_mass_acceleration(body, source, self.gravity_const);
+                    (a.0 - point_mass.0, a.1 - point_mass.1)
+                } else {
+                    self.force_law.acceleration(body, source, self.gravity_const, &self.softening)
+                };
+                let correction = self.relativity.acceleration(body, source, self.gravity_const);
+                acceleration.0 += a.0 + correction.0;
+                acceleration.1 += a.1 + correction.1;
+            }
+
+            // External fields act on everything
+            for field in &self.fields {
+                let a = field.acceleration(body.x, body.y, time, self.gravity_const);
+                acceleration.0 += a.0;
+                acceleration.1 += a.1;
+            }
+
+            // Pseudo-forces of a rotating frame
+            let a = self.frame.acceleration(body);
+            acceleration.0 += a.0;
+            acceleration.1 += a.1;
+            acceleration
+        }).collect()
+    }
+
+    // Time derivative of the (x, y, v_x, v_y) state of every body, with bodies that
+    // aren't free held at their prescribed positions
+    pub fn derivatives(&self, state: &[[f64; 4]], time: f64) -> State {
+        let mut bodies = self.bodies.clone();
+        for (body, values) in bodies.iter_mut().zip(state) {
+            body.x = values[0];
+            body.y = values[1];
+            body.v_x = values[2];
+            body.v_y = values[3];
+            if let Some(prescribed) = self.prescribed_state(&body.motion, time) {
+                body.set_state(prescribed);
+            }
+        }
+
+        let accelerations = self.accelerations(&bodies, time);
+        let mut derivatives: State = bodies.iter().zip(accelerations).map(|(body, acceleration)| {
+            if body.motion.is_free() {
+                [body.v_x, body.v_y, acceleration.0, acceleration.1]
+            } else {
+                [0.0; 4]
+            }
+        }).collect();
+
+        // The relative motion of close pairs is drifted separately, so here they only move
+        // with their centre of mass
+        for &(i, j) in &self.close_pairs {
+            let (first, second) = (&bodies[i], &bodies[j]);
+            let total_mass = first.mass + second.mass;
+            let velocity = ((first.mass * first.v_x + second.mass * second.v_x) / total_mass, (first.mass * first.v_y + second.mass * second.v_y) / total_mass);
+            for &k in &[i, j] {
+                derivatives[k][0] = velocity.0;
+                derivatives[k][1] = velocity.1;
+            }
+        }
+        derivatives
+    }
+
+    // Merges overlapping massive bodies, breaking up the product of violent impacts
+    // between free bodies other than fresh fragments, and lets massive bodies absorb the test particles that hit them
+    fn resolve_collisions(&mut self, time: f64) {
+        let mut removed = vec![false; self.bodies.len()];
+        let mut fragments = vec![];
+        self.spared.retain(|&(_, until)| until > time);
+        let massive: Vec<usize> = (0..self.bodies.len()).filter(|&i| self.bodies[i].class == BodyClass::Massive).collect();
+
+        for (index, &i) in massive.iter().enumerate() {
+            for &j in &massive[index + 1..] {
+                if removed[i] {
+                    break;
                 }
+                if removed[j] || !self.bodies[i].overlaps(&self.bodies[j]) {
+                    continue;
+                }
+                let (body_i, body_j) = (self.bodies[i], self.bodies[j]);
+
+                // Bodies that aren't free always survive a collision with a free body
+                let i_survives = if body_i.motion.is_free() != body_j.motion.is_free() {
+                    !body_i.motion.is_free()
+                } else {
+                    body_i.mass >= body_j.mass
+                };
+                let (bigger_index, smaller_index) = if i_survives {
+                    (i, j)
+                } else {
+                    (j, i)
+                };
+
+                let (bigger, smaller) = (self.bodies[bigger_index], self.bodies[smaller_index]);
+                let speed = impact_speed(&bigger, &smaller);
+                self.events.emit(Event::Collision { first: body_i.id, second: body_j.id, impact_speed: speed });
+                self.events.emit(Event::Merged { survivor: bigger.id, absorbed: smaller.id });
+                removed[smaller_index] = true;
+                self.bodies[bigger_index].merge(&smaller);
+
+                let specific_energy = fragmentation::specific_impact_energy(&bigger, &smaller);
+                let fragmentation = self.fragmentation;
+                let is_spared = self.spared.iter().any(|&(id, _)| id == bigger.id || id == smaller.id);
+                let mut pieces = if fragmentation.is_enabled() && bigger.motion.is_free() && smaller.motion.is_free() && !is_spared && specific_energy > fragmentation.threshold {
+                    fragmentation.fragment(&mut self.bodies[bigger_index], &smaller, specific_energy, self.gravity_const)
+                } else {
+                    vec![]
+                };
+
+                // What the fragments didn't carry off heats the remnant and fragments alike
+                let remnant = self.bodies[bigger_index];
+                let mass = remnant.mass + pieces.iter().fold(0.0, |sum, piece| sum + piece.mass);
+                let ejected_energy = fragmentation::internal_kinetic_energy(&[&pieces[..], &[remnant]].concat());
+                let heat = (specific_energy - ejected_energy / mass).max(0.0);
+                for body in pieces.iter_mut().chain(Some(&mut self.bodies[bigger_index])) {
+                    self.thermal.heat(body, heat);
+                }
+                let until = time + fragmentation::cooldown(&remnant, &pieces);
+                if !pieces.is_empty() {
+                    self.events.emit(Event::Fragmented { remnant: bigger.id, fragments: pieces.len() });
+                    self.spared.push((bigger.id, until));
+                }
+                self.mergers.record(Merger {
+                    time,
+                    survivor: bigger.id,
+                    absorbed: smaller.id,
+                    survivor_mass: bigger.mass,
+                    absorbed_mass: smaller.mass,
+                    ejected_mass: pieces.iter().fold(0.0, |sum, piece| sum + piece.mass),
+                    impact_speed: speed,
+                });
+                fragments.extend(pieces.into_iter().map(|piece| (piece, until)));
             }
-            // Update Self
-            self.bodies[i].update_self(mult, total_time);
         }
 
-        // Remove bodies
-        let mut remaining_bodies: Vec<Body> = self.bodies
-            .drain(..)
-            .enumerate()
-            .filter_map(|(index, body)| {
-                if to_remove.contains(&index) { None }
-                else { Some(body) }
-            })
+        for i in 0..self.bodies.len() {
+            if self.bodies[i].class != BodyClass::Test {
+                continue;
+            }
+            let body = self.bodies[i];
+            if let Some(&j) = massive.iter().find(|&&j| !removed[j] && body.overlaps(&self.bodies[j])) {
+                self.events.emit(Event::Collision { first: self.bodies[j].id, second: body.id, impact_speed: impact_speed(&self.bodies[j], &body) });
+                self.events.emit(Event::Merged { survivor: self.bodies[j].id, absorbed: body.id });
+                removed[i] = true;
+            }
+        }
+
+        self.remove_bodies(&removed);
+        for (fragment, until) in fragments {
+            let id = self.add_body(fragment);
+            self.debris.push(id);
+            self.spared.push((id, until));
+        }
+    }
+
+    // Tears apart bodies that came within the Roche limit of a heavier body
+    fn disrupt_satellites(&mut self) {
+        let disruptions = self.roche.disruptions(&self.bodies, &self.debris);
+        if disruptions.is_empty() {
+            return;
+        }
+        let mut removed = vec![false; self.bodies.len()];
+        let mut pieces = vec![];
+        for (i, primary) in disruptions {
+            let (body, primary) = (self.bodies[i], self.bodies[primary]);
+            let fragments = self.roche.break_up(&body, &primary, self.gravity_const);
+            if fragments.is_empty() {
+                continue;
+            }
+            self.events.emit(Event::Disrupted { body: body.id, primary: primary.id, fragments: fragments.len() });
+            removed[i] = true;
+            pieces.extend(fragments);
+        }
+
+        self.remove_bodies(&removed);
+        for piece in pieces {
+            let id = self.add_body(piece);
+            self.debris.push(id);
+        }
+    }
+
+    // Records escapes and binaries that formed or broke up since the last update, then
+    // removes the escapees if asked to
+    fn detect_encounters(&mut self) {
+        let ids: Vec<BodyId> = self.bodies.iter().map(|body| body.id).collect();
+
+        let escaped: Vec<BodyId> = self.escape.escapees(&self.bodies, self.gravity_const).iter().map(|&i| ids[i]).collect();
+        for &id in &escaped {
+            if !self.escaped.contains(&id) {
+                self.events.emit(Event::Escaped(id));
+            }
+        }
+        self.escaped = escaped;
+
+        // Binaries whose bodies are gone came to an end with them rather than coming apart
+        if self.detect_binaries {
+            let binaries: Vec<(BodyId, BodyId)> = escape::binaries(&self.bodies, self.gravity_const).iter().map(|&(i, j)| (ids[i], ids[j])).collect();
+            for &(first, second) in &binaries {
+                if !self.binaries.contains(&(first, second)) {
+                    self.events.emit(Event::Bound(first, second));
+                }
+            }
+            for &(first, second) in &self.binaries {
+                if !binaries.contains(&(first, second)) && ids.contains(&first) && ids.contains(&second) {
+                    self.events.emit(Event::Unbound(first, second));
+                }
+            }
+            self.binaries = binaries;
+        } else {
+            self.binaries.clear();
+        }
+
+        if self.escape.remove && !self.escaped.is_empty() {
+            let removed: Vec<bool> = ids.iter().map(|id| self.escaped.contains(id)).collect();
+            self.remove_bodies(&removed);
+        }
+    }
+
+    fn remove_bodies(&mut self, removed: &[bool]) {
+        // Pairs refer to bodies by index, which shifts as bodies are removed
+        let mut new_index = vec![0; removed.len()];
+        let mut count = 0;
+        for (i, &gone) in removed.iter().enumerate() {
+            new_index[i] = count;
+            if !gone {
+                count += 1;
+            }
+        }
+        self.close_pairs = self.close_pairs.iter()
+            .filter(|&&(i, j)| !removed[i] && !removed[j])
+            .map(|&(i, j)| (new_index[i], new_index[j]))
             .collect();
-        self.bodies.append(&mut remaining_bodies);
+
+        let gone: Vec<BodyId> = (0..removed.len()).filter(|&i| removed[i]).map(|i| self.bodies[i].id).collect();
+        self.binaries.retain(|&(first, second)| !gone.contains(&first) && !gone.contains(&second));
+        self.escaped.retain(|id| !gone.contains(id));
+        self.debris.retain(|id| !gone.contains(id));
+        self.spared.retain(|&(id, _)| !gone.contains(&id));
+
+        // Remove bodies
+        for i in (0..removed.len()).filter(|&i| removed[i]) {
+            self.events.emit(Event::Removed(Box::new(self.bodies[i])));
+        }
+        let mut index = 0;
+        self.bodies.retain(|_| {
+            index += 1;
+            !removed[index - 1]
+        });
+    }
+
+    pub fn hierarchy(&self) -> Hierarchy {
+        Hierarchy::new(&self.bodies, self.influence)
+    }
+
+    // Two-body orbital elements of the body at `index` around the body at `primary`
+    pub fn elements_of(&self, index: usize, primary: usize) -> Elements {
+        let (body, primary) = (&self.bodies[index], &self.bodies[primary]);
+        let mass = if body.class == BodyClass::Massive { body.mass } else { 0.0 };
+        let (velocity, primary_velocity) = (body.synchronised_velocity(), primary.synchronised_velocity());
+        Elements::from_state(
+            (body.x - primary.x, body.y - primary.y),
+            (velocity.0 - primary_velocity.0, velocity.1 - primary_velocity.1),
+            self.gravity_const * (primary.mass + mass))
+    }
+
+    // Lagrange points L1 to L5 of the pair of bodies at `i` and `j`, with the velocity of a
+    // particle co-rotating with the pair
+    pub fn lagrange_points(&self, i: usize, j: usize) -> [((f64, f64), (f64, f64)); 5] {
+        lagrange::lagrange_points(&self.bodies[i], &self.bodies[j])
+    }
+
+    // Adds a test particle at each Lagrange point of the pair at `i` and `j`
+    pub fn spawn_at_lagrange_points(&mut self, i: usize, j: usize) {
+        for &((x, y), (v_x, v_y)) in self.lagrange_points(i, j).iter() {
+            self.add_body(Body::test_particle(x, y, v_x, v_y, 0.0));
+        }
+    }
+
+    // Jacobi constant of the body at `index` in a rotating frame
+    pub fn jacobi_constant(&self, index: usize) -> f64 {
+        self.frame.jacobi_constant(&self.bodies, index, self.gravity_const)
+    }
+
+    // Position and velocity at the given time of a body on a kinematic path
+    pub fn prescribed_state(&self, motion: &Motion, time: f64) -> Option<((f64, f64), (f64, f64))> {
+        match *motion {
+            Motion::Free | Motion::Pinned => None,
+            Motion::Kepler(orbit) => Some(orbit.state_at(time)),
+            Motion::Scripted(index) => Some(self.paths[index].state_at(time)),
+        }
     }
 
     pub fn render(&self, canvas: &mut WindowCanvas, cam: &Cam) {
         self.bodies.iter().for_each(|body| body.render(canvas, cam));
     }
+
+    // Draws the osculating conic of the body at `index` around `primary`, with markers at
+    // periapsis and, for bound orbits, apoapsis
+    pub fn render_orbit(&self, canvas: &mut WindowCanvas, cam: &Cam, index: usize, primary: usize) {
+        let elements = self.elements_of(index, primary);
+        let focus = (self.bodies[primary].x, self.bodies[primary].y);
+        let direction = (elements.periapsis_arg.cos(), elements.periapsis_arg.sin());
+        let color = Color::RGBA(120, 200, 255, 150);
+        let screen = |point: (f64, f64)| {
+            let t = cam.transform(point);
+            Point::new(t.0 as i32, t.1 as i32)
+        };
+
+        if elements.is_bound() {
+            let a = elements.semi_major;
+            let b = a * (1.0 - elements.eccentricity.powi(2)).sqrt();
+            let center = (focus.0 - a * elements.eccentricity * direction.0, focus.1 - a * elements.eccentricity * direction.1);
+            canvas.rotated_ellipse_rgba(screen(center), a * cam.zoom, b * cam.zoom, elements.periapsis_arg, color).expect("Failed to draw orbit");
+
+            let apoapsis = (focus.0 - elements.apoapsis() * direction.0, focus.1 - elements.apoapsis() * direction.1);
+            canvas.filled_circle(screen(apoapsis), 4, Color::RGBA(255, 150, 100, 255)).expect("Failed to draw apoapsis");
+        } else {
+            // The branch of the hyperbola, out to where the body is ten times further away
+            // than its periapsis or itself, whichever is further
+            let distance = ((self.bodies[index].x - focus.0).powi(2) + (self.bodies[index].y - focus.1).powi(2)).sqrt();
+            let semi_latus = elements.semi_major * (1.0 - elements.eccentricity.powi(2));
+            let max_distance = 10.0 * distance.max(elements.periapsis());
+            let limit = ((semi_latus / max_distance - 1.0) / elements.eccentricity).max(-1.0).acos();
+            let side = if elements.retrograde { -1.0 } else { 1.0 };
+            let segments = 200;
+            let mut previous = None;
+            for i in 0..segments + 1 {
+                let anomaly = -limit + 2.0 * limit * i as f64 / segments as f64;
+                let radius = semi_latus / (1.0 + elements.eccentricity * anomaly.cos());
+                let angle = elements.periapsis_arg + side * anomaly;
+                let point = screen((focus.0 + radius * angle.cos(), focus.1 + radius * angle.sin()));
+                if let Some(previous) = previous {
+                    canvas.line_rgba(previous, point, color).expect("Failed to draw orbit");
+                }
+                previous = Some(point);
+            }
+        }
+
+        let periapsis = (focus.0 + elements.periapsis() * direction.0, focus.1 + elements.periapsis() * direction.1);
+        canvas.filled_circle(screen(periapsis), 4, Color::RGBA(100, 255, 150, 255)).expect("Failed to draw periapsis");
+    }
+
+    // Marks the Lagrange points of the pair at `i` and `j`, the unstable collinear points
+    // L1 to L3 with crosses and the triangular points L4 and L5 with circles
+    pub fn render_lagrange_points(&self, canvas: &mut WindowCanvas, cam: &Cam, i: usize, j: usize) {
+        let color = Color::RGBA(255, 120, 200, 200);
+        for (index, &(position, _)) in self.lagrange_points(i, j).iter().enumerate() {
+            let t = cam.transform(position);
+            let (x, y) = (t.0 as i32, t.1 as i32);
+            if index < 3 {
+                canvas.line_rgba(Point::new(x - 5, y - 5), Point::new(x + 5, y + 5), color).expect("Failed to draw Lagrange point");
+                canvas.line_rgba(Point::new(x - 5, y + 5), Point::new(x + 5, y - 5), color).expect("Failed to draw Lagrange point");
+            } else {
+                canvas.rotated_ellipse_rgba(Point::new(x, y), 5.0, 5.0, 0.0, color).expect("Failed to draw Lagrange point");
+            }
+        }
+    }
+
+    // Traces the zero-velocity curves 2 U = `jacobi` of a rotating frame across the screen
+    // by marching squares, bounding the region a body with that Jacobi constant can reach
+    pub fn render_zero_velocity_curves(&self, canvas: &mut WindowCanvas, cam: &Cam, jacobi: f64) {
+        let (width, height) = canvas.output_size().expect("Failed to get canvas size");
+        let step = 6;
+        let (columns, rows) = (width as i32 / step + 2, height as i32 / step + 2);
+        let values: Vec<f64> = (0..rows * columns).map(|k| {
+            let point = cam.reverse_transform(((k % columns * step) as f64, (k / columns * step) as f64));
+            2.0 * self.frame.effective_potential(point.0, point.1, &self.bodies, None, self.gravity_const) - jacobi
+        }).collect();
+        let color = Color::RGBA(255, 200, 80, 200);
+
+        for row in 0..rows - 1 {
+            for column in 0..columns - 1 {
+                // Corners clockwise from the top left, and where the curve crosses each edge
+                let corners = [(column, row), (column + 1, row), (column + 1, row + 1), (column, row + 1)];
+                let value = |corner: (i32, i32)| values[(corner.1 * columns + corner.0) as usize];
+                let mut crossings = vec![];
+                for edge in 0..4 {
+                    let (a, b) = (corners[edge], corners[(edge + 1) % 4]);
+                    let (value_a, value_b) = (value(a), value(b));
+                    if (value_a < 0.0) != (value_b < 0.0) {
+                        let mut t = value_a / (value_a - value_b);
+                        if !t.is_finite() {
+                            t = 0.5;
+                        }
+                        let x = (a.0 as f64 + t * (b.0 - a.0) as f64) * step as f64;
+                        let y = (a.1 as f64 + t * (b.1 - a.1) as f64) * step as f64;
+                        crossings.push(Point::new(x as i32, y as i32));
+                    }
+                }
+                for segment in crossings.chunks(2) {
+                    if segment.len() == 2 {
+                        canvas.line_rgba(segment[0], segment[1], color).expect("Failed to draw zero-velocity curve");
+                    }
+                }
+            }
+        }
+    }
+
+    // Outlines the sphere of influence of every body that has a finite one, fading with
+    // the depth in the hierarchy
+    pub fn render_influence(&self, canvas: &mut WindowCanvas, cam: &Cam, hierarchy: &Hierarchy) {
+        for (i, body) in self.bodies.iter().enumerate() {
+            let radius = hierarchy.radii[i];
+            if radius <= 0.0 || radius.is_infinite() {
+                continue;
+            }
+            let alpha = 160 / hierarchy.depth(i).clamp(1, 4) as u8;
+            let t = cam.transform((body.x, body.y));
+            canvas.rotated_ellipse_rgba(Point::new(t.0 as i32, t.1 as i32), radius * cam.zoom, radius * cam.zoom, 0.0, Color::RGBA(255, 220, 120, alpha)).expect("Failed to draw sphere of influence");
+        }
+    }
+}
+
+// Relative speed of two bodies
+fn impact_speed(first: &Body, second: &Body) -> f64 {
+    ((first.v_x - second.v_x).powi(2) + (first.v_y - second.v_y).powi(2)).sqrt()
+}
+
+// Mutable references to two different bodies
+fn pair_mut(bodies: &mut [Body], i: usize, j: usize) -> (&mut Body, &mut Body) {
+    if i < j {
+        let (head, tail) = bodies.split_at_mut(j);
+        (&mut head[i], &mut tail[0])
+    } else {
+        let (head, tail) = bodies.split_at_mut(i);
+        (&mut tail[0], &mut head[j])
+    }
 }
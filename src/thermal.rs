@@ -0,0 +1,61 @@
+use body::Body;
+use units::UnitSystem;
+
+// Heating of bodies by impacts and their cooling by thermal radiation. All bodies share
+// the specific `heat_capacity`, so the kinetic energy an impact dissipates raises the
+// temperature of the product by the specific impact energy over it. Bodies then radiate
+// from their surface as grey bodies of the given `emissivity`, following the Stefan-
+// Boltzmann law of the unit system. Point masses have no material to heat up.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Thermal {
+    pub heat_capacity: f64,
+    pub emissivity: f64,
+    pub stefan_boltzmann: f64,
+}
+
+impl Thermal {
+    pub fn new(heat_capacity: f64, emissivity: f64, stefan_boltzmann: f64) -> Thermal {
+        Thermal {
+            heat_capacity,
+            emissivity,
+            stefan_boltzmann,
+        }
+    }
+
+    // Rock-like bodies in the given units
+    pub fn of_units(units: UnitSystem) -> Thermal {
+        Thermal::new(units.heat_capacity(), 1.0, units.stefan_boltzmann())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.heat_capacity > 0.0
+    }
+
+    pub fn heat(&self, body: &mut Body, specific_energy: f64) {
+        if self.is_enabled() && body.size > 0.0 {
+            body.temperature += specific_energy / self.heat_capacity;
+        }
+    }
+
+    // Radiates over `dt`. With the emitted power growing as T^4 the temperature falls
+    // as (T0^-3 + 3 a t)^(-1/3), which is exact however long the step.
+    pub fn cool(&self, body: &mut Body, dt: f64) {
+        if !self.is_enabled() || body.size <= 0.0 || body.mass <= 0.0 || body.temperature <= 0.0 {
+            return;
+        }
+        let area = 4.0 * ::PI * body.size.powi(2);
+        let rate = self.emissivity * self.stefan_boltzmann * area / (self.heat_capacity * body.mass);
+        body.temperature = (body.temperature.powi(-3) + 3.0 * rate * dt).powf(-1.0 / 3.0);
+    }
+}
+
+// Colour of a black body at `temperature` in kelvin, after the fit of Tanner Helland to
+// the blackbody spectrum seen through the CIE colour matching functions
+pub fn blackbody_color(temperature: f64) -> (u8, u8, u8) {
+    let t = (temperature / 100.0).max(1.0);
+    let channel = |value: f64| value.clamp(0.0, 255.0) as u8;
+    let red = if t <= 66.0 { 255.0 } else { 329.698727446 * (t - 60.0).powf(-0.1332047592) };
+    let green = if t <= 66.0 { 99.4708025861 * t.ln() - 161.1195681661 } else { 288.1221695283 * (t - 60.0).powf(-0.0755148492) };
+    let blue = if t >= 66.0 { 255.0 } else if t <= 19.0 { 0.0 } else { 138.5177312231 * (t - 10.0).ln() - 305.0447927307 };
+    (channel(red), channel(green), channel(blue))
+}
@@ -0,0 +1,147 @@
+// Unit systems the simulation can run in. G is derived from the choice of length, mass
+// and time units so that bodies can be specified with real data.
+#[derive(Copy, Clone, PartialEq)]
+pub enum UnitSystem {
+    Pixel,
+    NBody,
+    Si,
+    Astronomical,
+}
+
+impl UnitSystem {
+    pub fn from_name(name: &str) -> Option<UnitSystem> {
+        match name.to_lowercase().as_str() {
+            "pixel" | "px" => Some(UnitSystem::Pixel),
+            "nbody" | "n-body" | "dimensionless" => Some(UnitSystem::NBody),
+            "si" => Some(UnitSystem::Si),
+            "astronomical" | "au" => Some(UnitSystem::Astronomical),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            UnitSystem::Pixel => "PIXEL",
+            UnitSystem::NBody => "N-BODY",
+            UnitSystem::Si => "SI",
+            UnitSystem::Astronomical => "AU/MSUN/YR",
+        }
+    }
+
+    pub fn next(&self) -> UnitSystem {
+        match *self {
+            UnitSystem::Pixel => UnitSystem::NBody,
+            UnitSystem::NBody => UnitSystem::Si,
+            UnitSystem::Si => UnitSystem::Astronomical,
+            UnitSystem::Astronomical => UnitSystem::Pixel,
+        }
+    }
+
+    pub fn gravity_const(&self) -> f64 {
+        match *self {
+            UnitSystem::Pixel => 0.0005,
+            UnitSystem::NBody => 1.0,
+            UnitSystem::Si => 6.674_30e-11,
+            // AU^3 / (Msun yr^2), from Kepler's third law
+            UnitSystem::Astronomical => 4.0 * ::PI.powi(2),
+        }
+    }
+
+    pub fn speed_of_light(&self) -> f64 {
+        match *self {
+            // Low enough for relativistic effects to show up at editor speeds
+            UnitSystem::Pixel => 30.0,
+            UnitSystem::NBody => 100.0,
+            UnitSystem::Si => 299_792_458.0,
+            UnitSystem::Astronomical => 63_241.077,
+        }
+    }
+
+    // Specific heat capacity of rock, about 1000 J / (kg K), where the invented units get
+    // one that lets impacts at editor speeds heat bodies by a few thousand kelvin
+    pub fn heat_capacity(&self) -> f64 {
+        match *self {
+            UnitSystem::Pixel => 1.0e-4,
+            UnitSystem::NBody => 1.0e-4,
+            UnitSystem::Si => 1000.0,
+            UnitSystem::Astronomical => 4.450e-5,
+        }
+    }
+
+    // Stefan-Boltzmann constant, high enough in the invented units for hot bodies to
+    // cool within seconds
+    pub fn stefan_boltzmann(&self) -> f64 {
+        match *self {
+            UnitSystem::Pixel => 1.0e-15,
+            UnitSystem::NBody => 1.0e-13,
+            UnitSystem::Si => 5.670_374e-8,
+            UnitSystem::Astronomical => 8.960e-16,
+        }
+    }
+
+    // Simulated time units that pass per second of wall clock time
+    pub fn time_scale(&self) -> f64 {
+        match *self {
+            UnitSystem::Pixel => 400.0,
+            UnitSystem::NBody => 1.0,
+            UnitSystem::Si => 86_400.0,
+            UnitSystem::Astronomical => 0.25,
+        }
+    }
+
+    pub fn length_unit(&self) -> &'static str {
+        match *self {
+            UnitSystem::Pixel => "px",
+            UnitSystem::NBody => "L",
+            UnitSystem::Si => "m",
+            UnitSystem::Astronomical => "AU",
+        }
+    }
+
+    pub fn mass_unit(&self) -> &'static str {
+        match *self {
+            UnitSystem::Pixel => "mu",
+            UnitSystem::NBody => "M",
+            UnitSystem::Si => "kg",
+            UnitSystem::Astronomical => "Msun",
+        }
+    }
+
+    pub fn time_unit(&self) -> &'static str {
+        match *self {
+            UnitSystem::Pixel => "t",
+            UnitSystem::NBody => "T",
+            UnitSystem::Si => "s",
+            UnitSystem::Astronomical => "yr",
+        }
+    }
+
+    pub fn format_length(&self, value: f64) -> String {
+        format!("{} {}", format_value(value), self.length_unit())
+    }
+
+    pub fn format_mass(&self, value: f64) -> String {
+        format!("{} {}", format_value(value), self.mass_unit())
+    }
+
+    pub fn format_time(&self, value: f64) -> String {
+        format!("{} {}", format_value(value), self.time_unit())
+    }
+
+    pub fn format_velocity(&self, value: f64) -> String {
+        format!("{} {}/{}", format_value(value), self.length_unit(), self.time_unit())
+    }
+
+    // Energy per unit mass
+    pub fn format_specific_energy(&self, value: f64) -> String {
+        format!("{} {}^2/{}^2", format_value(value), self.length_unit(), self.time_unit())
+    }
+}
+
+fn format_value(value: f64) -> String {
+    if value != 0.0 && (value.abs() >= 1.0e4 || value.abs() < 1.0e-2) {
+        format!("{:.3e}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}